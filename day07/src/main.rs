@@ -1,70 +1,57 @@
-use intcode::{program, Intcode};
+use intcode::scheduler::{Policy, Scheduler};
+use intcode::Intcode;
 use itertools::Itertools;
 use std::error::Error;
 use std::fs;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let input = fs::read_to_string("./src/input.txt")?;
-    println!("part 1: {}", amplify(&input.trim()));
+    println!("part 1: {}", amplify(input.trim()));
 
     // Part 2.
-    assert_eq!(35993240, feedback_loop(&input.trim()));
+    assert_eq!(35993240, feedback_loop(input.trim()));
 
     Ok(())
 }
 
-fn amplify(input: &str) -> i128 {
-    let mut max: i128 = i128::MIN;
-
-    for phases in (0..=4).permutations(5) {
-        let mut i: i128 = 0;
-        for phase in phases {
-            i = program(input, vec![phase, i]);
-        }
-        if i > max {
-            max = i;
+// Connects one amplifier per phase in series. Amplifier i reads channel i and writes channel
+// i + 1, and the last one writes either to "out" or back to the first amplifier.
+fn signal(input: &str, phases: &[i128], feedback: bool) -> i128 {
+    let n = phases.len();
+    let channel = |i: usize| {
+        if i == n && !feedback {
+            "out".to_string()
+        } else {
+            (i % n).to_string()
         }
+    };
+
+    let mut scheduler = Scheduler::new(Policy::RunUntilBlocked);
+    for (i, &phase) in phases.iter().enumerate() {
+        let program = Intcode::new(input, vec![phase]);
+        scheduler.add(&i.to_string(), program, &channel(i), &channel(i + 1));
     }
+    scheduler.send(&channel(0), 0);
+    scheduler.run().unwrap();
 
-    max
+    // The last signal is left unread when the amplifiers halt.
+    *scheduler.channel(&channel(n)).last().unwrap()
 }
 
-fn feedback_loop(input: &str) -> i128 {
-    let mut max: i128 = i128::MIN;
-
-    for phases in (5..=9).permutations(5) {
-        let mut out: i128 = 0;
-        let mut programs: Vec<Intcode> = vec![
-            Intcode::new(input, vec![phases[0], 0]),
-            Intcode::new(input, vec![phases[1]]),
-            Intcode::new(input, vec![phases[2]]),
-            Intcode::new(input, vec![phases[3]]),
-            Intcode::new(input, vec![phases[4]]),
-        ];
-        'inner: loop {
-            for i in 0..5 {
-                if programs[i].waiting {
-                    continue;
-                }
-                let output = programs[i].exec();
-                if programs[i].done {
-                    break 'inner;
-                }
+fn amplify(input: &str) -> i128 {
+    (0..=4)
+        .permutations(5)
+        .map(|phases| signal(input, &phases, false))
+        .max()
+        .unwrap()
+}
 
-                match output {
-                    Some(n) => {
-                        programs[(i + 1) % 5].set_input(n);
-                        out = n;
-                    }
-                    None => continue,
-                }
-            }
-        }
-        if out > max {
-            max = out;
-        }
-    }
-    max
+fn feedback_loop(input: &str) -> i128 {
+    (5..=9)
+        .permutations(5)
+        .map(|phases| signal(input, &phases, true))
+        .max()
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -74,22 +61,22 @@ mod tests {
     #[test]
     fn part1() {
         let input = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
-        assert_eq!(43210, amplify(input.into()));
+        assert_eq!(43210, amplify(input));
 
         let input = "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0";
-        assert_eq!(54321, amplify(input.into()));
+        assert_eq!(54321, amplify(input));
 
         let input = "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0";
-        assert_eq!(65210, amplify(input.into()));
+        assert_eq!(65210, amplify(input));
     }
 
     #[test]
     fn part2() {
         let input =
             "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
-        assert_eq!(139629729, feedback_loop(input.into()));
+        assert_eq!(139629729, feedback_loop(input));
 
         let input = "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10";
-        assert_eq!(18216, feedback_loop(input.into()));
+        assert_eq!(18216, feedback_loop(input));
     }
 }
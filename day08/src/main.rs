@@ -8,11 +8,11 @@ const PIXELS: usize = WIDTH * HEIGHT;
 fn main() -> Result<(), Box<dyn Error>> {
     let input = fs::read_to_string("./src/input.txt")?;
 
-    println!("part 1: {}", validate(&input.trim()));
+    println!("part 1: {}", validate(input.trim()));
 
-    for row in encode(&input.trim()) {
-        println!("{:?}", row);
-    }
+    let message = ocr::from_rows(&encode(input.trim()))?;
+    println!("part 2: {}", message);
+    assert_eq!("PHPEU", message);
 
     Ok(())
 }
@@ -70,4 +70,10 @@ mod tests {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn message() {
+        let input = include_str!("./input.txt").trim();
+        assert_eq!(Ok("PHPEU".to_string()), ocr::from_rows(&encode(input)));
+    }
 }
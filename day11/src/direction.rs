@@ -0,0 +1,54 @@
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Direction {
+    UP = 0,
+    RIGHT = 1,
+    DOWN = 2,
+    LEFT = 3,
+}
+
+impl Direction {
+    pub fn turn(self, left_or_right: i32) -> Self {
+        let next = match left_or_right {
+            0 => self as i32 - 1,
+            1 => self as i32 + 1,
+            _ => unimplemented!(),
+        };
+        Direction::from((next + 4) % 4)
+    }
+}
+
+impl From<i32> for Direction {
+    fn from(n: i32) -> Self {
+        use Direction::*;
+        match n {
+            0 => UP,
+            1 => RIGHT,
+            2 => DOWN,
+            3 => LEFT,
+            _ => UP,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn turn_right() {
+        assert_eq!(RIGHT, UP.turn(1));
+        assert_eq!(DOWN, RIGHT.turn(1));
+        assert_eq!(LEFT, DOWN.turn(1));
+        assert_eq!(UP, LEFT.turn(1));
+    }
+
+    #[test]
+    fn turn_left() {
+        assert_eq!(LEFT, UP.turn(0));
+        assert_eq!(DOWN, LEFT.turn(0));
+        assert_eq!(RIGHT, DOWN.turn(0));
+        assert_eq!(UP, RIGHT.turn(0));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::direction::Direction;
+use intcode::frame::{self, Frame};
+use intcode::{Intcode, Signal};
+use std::collections::HashMap;
+use std::error::Error;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Color {
+    Black = 0,
+    White = 1,
+}
+
+// What the robot outputs after every camera reading: the color to paint the panel under it, and
+// which way to turn (0 for left, 1 for right) before moving forward.
+struct Paint {
+    color: Color,
+    turn: i32,
+}
+
+impl Frame for Paint {
+    const WIDTH: usize = 2;
+
+    fn decode(values: &[i128]) -> Result<Self, String> {
+        let color = match values[0] {
+            0 => Color::Black,
+            1 => Color::White,
+            n => return Err(format!("unknown color {}", n)),
+        };
+        match values[1] {
+            turn @ (0 | 1) => Ok(Paint {
+                color,
+                turn: turn as i32,
+            }),
+            n => Err(format!("unknown turn {}", n)),
+        }
+    }
+}
+
+pub struct HullRobot {
+    program: Intcode,
+    pos: (i32, i32),
+    dir: Direction,
+    // Color of every panel that is not black by default: the painted ones and the start.
+    panels: HashMap<(i32, i32), Color>,
+    // Number of times each panel has been painted.
+    visits: HashMap<(i32, i32), usize>,
+}
+
+impl HullRobot {
+    // Places the robot facing up on a panel of the given color. y grows downwards.
+    pub fn new(code: &str, start: Color) -> Self {
+        HullRobot {
+            program: Intcode::new(code, vec![]),
+            pos: (0, 0),
+            dir: Direction::UP,
+            panels: HashMap::from([((0, 0), start)]),
+            visits: HashMap::new(),
+        }
+    }
+
+    pub fn color(&self, pos: (i32, i32)) -> Color {
+        self.panels.get(&pos).copied().unwrap_or(Color::Black)
+    }
+
+    // Runs the robot until its program halts.
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            self.program.input.push(self.color(self.pos) as i128);
+            let signal = self.program.exec();
+
+            let output = self.program.output.drain(..).collect::<Vec<i128>>();
+            for paint in frame::decode::<Paint>(&output)? {
+                self.panels.insert(self.pos, paint.color);
+                *self.visits.entry(self.pos).or_insert(0) += 1;
+
+                use Direction::*;
+                self.dir = self.dir.turn(paint.turn);
+                self.pos = match self.dir {
+                    UP => (self.pos.0, self.pos.1 - 1),
+                    DOWN => (self.pos.0, self.pos.1 + 1),
+                    LEFT => (self.pos.0 - 1, self.pos.1),
+                    RIGHT => (self.pos.0 + 1, self.pos.1),
+                };
+            }
+
+            if signal == Signal::Halt {
+                return Ok(());
+            }
+        }
+    }
+
+    // Number of panels painted at least once.
+    pub fn painted(&self) -> usize {
+        self.visits.len()
+    }
+
+    pub fn visits(&self) -> &HashMap<(i32, i32), usize> {
+        &self.visits
+    }
+
+    pub fn panels(&self) -> &HashMap<(i32, i32), Color> {
+        &self.panels
+    }
+
+    // Colors of every panel in the bounding box of the known panels, row by row.
+    pub fn grid(&self) -> Vec<Vec<Color>> {
+        let xs = self.panels.keys().map(|pos| pos.0);
+        let ys = self.panels.keys().map(|pos| pos.1);
+        let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+        let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+
+        (min_y..=max_y)
+            .map(|y| (min_x..=max_x).map(|x| self.color((x, y))).collect())
+            .collect()
+    }
+
+    // Renders white panels as `#` and black ones as `.`.
+    pub fn render(&self) -> String {
+        self.grid()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&color| if color == Color::White { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Renders the panels as a binary PPM with every panel `scale` pixels wide.
+    pub fn ppm(&self, scale: usize) -> Vec<u8> {
+        let grid = self.grid();
+        let scale = scale.max(1);
+        let (width, height) = (grid[0].len() * scale, grid.len() * scale);
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for y in 0..height {
+            for x in 0..width {
+                let shade = match grid[y / scale][x / scale] {
+                    Color::White => 255,
+                    Color::Black => 0,
+                };
+                image.extend([shade; 3]);
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paints white and turns left, right, then left, ignoring the camera.
+    const WANDER: &str = "3,100,104,1,104,0,3,100,104,1,104,1,3,100,104,1,104,0,99";
+
+    #[test]
+    fn negative_coordinates() {
+        let mut robot = HullRobot::new(WANDER, Color::Black);
+        robot.run().unwrap();
+
+        assert_eq!(3, robot.painted());
+        assert_eq!(Color::White, robot.color((-1, -1)));
+        assert_eq!("#.\n##", robot.render());
+
+        let image = robot.ppm(2);
+        assert!(image.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(11 + 4 * 4 * 3, image.len());
+        assert_eq!(&[255, 255, 255], &image[11..14]);
+        assert_eq!(&[0, 0, 0], &image[11 + 2 * 3..11 + 3 * 3]);
+    }
+
+    #[test]
+    fn visits() {
+        // Paints the start panel twice by turning around it.
+        let code = "3,100,104,1,104,1,3,100,104,0,104,1,3,100,104,1,104,1,3,100,104,1,104,1,3,100,104,0,104,1,99";
+        let mut robot = HullRobot::new(code, Color::White);
+        robot.run().unwrap();
+        assert_eq!(Some(&2), robot.visits().get(&(0, 0)));
+        assert_eq!(Color::Black, robot.color((0, 0)));
+        assert_eq!(4, robot.painted());
+    }
+
+    #[test]
+    fn invalid_output() {
+        let mut robot = HullRobot::new("104,2,104,0,99", Color::Black);
+        let err = robot.run().unwrap_err();
+        assert_eq!("invalid frame [2, 0]: unknown color 2", err.to_string());
+    }
+}
@@ -0,0 +1,311 @@
+use intcode::frame::{Frame, FrameError};
+use intcode::{Intcode, Signal};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    // Horizontal paddle.
+    Paddle,
+    Ball,
+}
+
+// What the game draws: a tile at (x, y), or the score when x is -1 and y is 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Draw {
+    Tile { x: i128, y: i128, tile: Tile },
+    Score(i128),
+}
+
+impl Frame for Draw {
+    const WIDTH: usize = 3;
+
+    fn decode(values: &[i128]) -> Result<Self, String> {
+        let (x, y) = match *values {
+            [-1, 0, score] => return Ok(Draw::Score(score)),
+            [x, y, _] => (x, y),
+            _ => unreachable!(),
+        };
+        let tile = match values[2] {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            n => return Err(format!("unknown tile {}", n)),
+        };
+        Ok(Draw::Tile { x, y, tile })
+    }
+}
+
+// Everything the game has drawn so far.
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+    tiles: HashMap<(i128, i128), Tile>,
+    pub score: i128,
+    // Number of times the game has refreshed the screen.
+    pub frames: usize,
+    ball: Option<(i128, i128)>,
+    previous_ball: Option<(i128, i128)>,
+    paddle: Option<(i128, i128)>,
+}
+
+impl Screen {
+    pub fn draw(&mut self, draw: Draw) {
+        match draw {
+            Draw::Score(n) => self.score = n,
+            Draw::Tile { x, y, tile } => {
+                match tile {
+                    Tile::Ball => {
+                        self.previous_ball = self.ball;
+                        self.ball = Some((x, y));
+                    }
+                    Tile::Paddle => self.paddle = Some((x, y)),
+                    _ => {}
+                }
+                self.tiles.insert((x, y), tile);
+            }
+        }
+    }
+
+    pub fn tile(&self, pos: (i128, i128)) -> Tile {
+        self.tiles.get(&pos).copied().unwrap_or(Tile::Empty)
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.tiles.values().filter(|&&tile| tile == Tile::Block).count()
+    }
+
+    pub fn ball(&self) -> Option<(i128, i128)> {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> Option<(i128, i128)> {
+        self.paddle
+    }
+
+    // Column where the falling ball reaches the row above the paddle, bouncing off the side
+    // walls. Blocks in the way are ignored, since the prediction is redone every frame. None
+    // while the ball is rising or has not moved yet.
+    pub fn landing(&self) -> Option<i128> {
+        let ((x, y), (px, py)) = (self.ball?, self.previous_ball?);
+        let (mut dx, dy) = (x - px, y - py);
+        let (_, paddle_y) = self.paddle?;
+        if dy <= 0 || dx == 0 {
+            return None;
+        }
+
+        let (mut x, mut y) = (x, y);
+        while y < paddle_y - 1 {
+            if self.tile((x + dx, y)) == Tile::Wall {
+                dx = -dx;
+            }
+            x += dx;
+            y += 1;
+        }
+        Some(x)
+    }
+
+    // One character per tile, with the score underneath.
+    pub fn render(&self) -> String {
+        self.render_with(|tile| {
+            match tile {
+                Tile::Empty => " ",
+                Tile::Wall => "#",
+                Tile::Block => "+",
+                Tile::Paddle => "-",
+                Tile::Ball => "o",
+            }
+            .to_string()
+        })
+    }
+
+    // Like `render`, in color, and starting with the escape sequence that clears the terminal so
+    // that frames replace each other.
+    pub fn ansi(&self) -> String {
+        let screen = self.render_with(|tile| match tile {
+            Tile::Empty => " ".to_string(),
+            Tile::Wall => "\x1b[90m#\x1b[0m".to_string(),
+            Tile::Block => "\x1b[33m+\x1b[0m".to_string(),
+            Tile::Paddle => "\x1b[36m-\x1b[0m".to_string(),
+            Tile::Ball => "\x1b[31mo\x1b[0m".to_string(),
+        });
+        format!("\x1b[H\x1b[2J{}", screen)
+    }
+
+    // Width and height of the area drawn so far, which starts at (0, 0).
+    pub fn size(&self) -> (usize, usize) {
+        let max_x = self.tiles.keys().map(|pos| pos.0 + 1).max().unwrap_or(0);
+        let max_y = self.tiles.keys().map(|pos| pos.1 + 1).max().unwrap_or(0);
+        (max_x.max(0) as usize, max_y.max(0) as usize)
+    }
+
+    fn render_with<F>(&self, glyph: F) -> String
+    where
+        F: Fn(Tile) -> String,
+    {
+        let (width, height) = self.size();
+        let mut lines = (0..height as i128)
+            .map(|y| {
+                (0..width as i128)
+                    .map(|x| glyph(self.tile((x, y))))
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        lines.push(format!(
+            "score: {}  blocks: {}  frame: {}",
+            self.score,
+            self.blocks(),
+            self.frames
+        ));
+        lines.join("\n")
+    }
+}
+
+// Joystick positions.
+pub const LEFT: i128 = -1;
+pub const NEUTRAL: i128 = 0;
+pub const RIGHT: i128 = 1;
+
+// Moves the paddle towards where the ball will land, or under the ball while it rises.
+pub fn autopilot(screen: &Screen) -> Option<i128> {
+    let (ball_x, _) = screen.ball()?;
+    let (paddle_x, _) = screen.paddle()?;
+    let target = screen.landing().unwrap_or(ball_x);
+    Some((target - paddle_x).signum())
+}
+
+// Reads a joystick position from a line typed by a human: `a`, `h` or the left arrow key for
+// left, `d`, `l` or the right arrow key for right, and anything else to stay put.
+pub fn keys(line: &str) -> i128 {
+    match line.trim_end_matches(['\r', '\n']) {
+        "a" | "h" | "\x1b[D" => LEFT,
+        "d" | "l" | "\x1b[C" => RIGHT,
+        _ => NEUTRAL,
+    }
+}
+
+pub struct Arcade {
+    program: Intcode,
+    pub screen: Screen,
+}
+
+impl Arcade {
+    pub fn new(code: &str) -> Self {
+        Arcade {
+            program: Intcode::new(code, vec![]),
+            screen: Screen::default(),
+        }
+    }
+
+    // Inserts the two quarters that let the game be played instead of only drawn.
+    pub fn free_play(code: &str) -> Self {
+        let mut arcade = Arcade::new(code);
+        arcade.program.poke(0, 2);
+        arcade
+    }
+
+    // Runs the game until it reads the joystick or ends, and draws what it outputs.
+    pub fn refresh(&mut self) -> Result<Signal, FrameError> {
+        let signal = self.program.exec();
+        let output = self.program.output.drain(..).collect::<Vec<i128>>();
+        for draw in intcode::frame::decode::<Draw>(&output)? {
+            self.screen.draw(draw);
+        }
+        self.screen.frames += 1;
+        Ok(signal)
+    }
+
+    // Plays until the game ends, or until `joystick` returns None. `joystick` sees every frame
+    // before it is asked for a position.
+    pub fn play<F>(&mut self, mut joystick: F) -> Result<Signal, FrameError>
+    where
+        F: FnMut(&Screen) -> Option<i128>,
+    {
+        loop {
+            if self.refresh()? == Signal::Halt {
+                return Ok(Signal::Halt);
+            }
+            match joystick(&self.screen) {
+                Some(position) => self.program.input.push(position),
+                None => return Ok(Signal::Waiting),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen() {
+        let mut screen = Screen::default();
+        for draw in [
+            Draw::Tile {
+                x: 0,
+                y: 0,
+                tile: Tile::Wall,
+            },
+            Draw::Tile {
+                x: 1,
+                y: 0,
+                tile: Tile::Block,
+            },
+            Draw::Tile {
+                x: 2,
+                y: 1,
+                tile: Tile::Ball,
+            },
+            Draw::Tile {
+                x: 1,
+                y: 2,
+                tile: Tile::Paddle,
+            },
+            Draw::Score(12),
+        ] {
+            screen.draw(draw);
+        }
+        assert_eq!(1, screen.blocks());
+        assert_eq!("#+ \n  o\n - \nscore: 12  blocks: 1  frame: 0", screen.render());
+        assert!(screen.ansi().starts_with("\x1b[H\x1b[2J"));
+    }
+
+    #[test]
+    fn landing() {
+        // The ball falls right and bounces off the wall at x = 5 before reaching row 9.
+        let mut screen = Screen::default();
+        for y in 0..10 {
+            screen.draw(Draw::Tile {
+                x: 5,
+                y,
+                tile: Tile::Wall,
+            });
+        }
+        screen.draw(Draw::Tile {
+            x: 2,
+            y: 10,
+            tile: Tile::Paddle,
+        });
+        for (x, y) in [(1, 1), (2, 2)] {
+            screen.draw(Draw::Tile {
+                x,
+                y,
+                tile: Tile::Ball,
+            });
+        }
+
+        // (3, 3), (4, 4), then back through (3, 5) to (-1, 9).
+        assert_eq!(Some(-1), screen.landing());
+        assert_eq!(Some(LEFT), autopilot(&screen));
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(LEFT, super::keys("a\n"));
+        assert_eq!(RIGHT, super::keys("\x1b[C\n"));
+        assert_eq!(NEUTRAL, super::keys("\n"));
+    }
+}
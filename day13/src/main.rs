@@ -1,89 +1,135 @@
-use intcode::{Intcode, Signal};
+use intcode::Signal;
+use std::env;
 use std::error::Error;
 use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+mod arcade;
+mod recorder;
+use arcade::*;
+use recorder::*;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let input = fs::read_to_string("./src/input.txt")?;
+    let input = input.trim();
 
-    let mut program = Intcode::from(input);
-    let output = program.run_until_halt();
-
-    let num_blocks = output
-        .iter()
-        .skip(2)
-        .step_by(3)
-        .filter(|&tile_id| tile_id == &2)
-        .count();
+    assert_eq!(207, part1(input)?);
 
-    assert_eq!(207, num_blocks);
-
-    // Part 2.
-    let input = fs::read_to_string("./src/input.txt")?;
-    let input = "2".to_owned() + &input[1..].to_owned();
-
-    let mut program = Intcode::from(input);
+    // Part 2. Pass `--play` to play with the keyboard, or `--watch` to see the autopilot play.
+    let args = env::args().collect::<Vec<String>>();
+    let mut arcade = Arcade::free_play(input);
+    if args.iter().any(|arg| arg == "--play") {
+        let mut lines = io::stdin().lock().lines();
+        arcade.play(|screen| {
+            println!("{}", screen.ansi());
+            println!("a/h/left, d/l/right, then enter:");
+            lines.next()?.ok().map(|line| keys(&line))
+        })?;
+        println!("{}", arcade.screen.ansi());
+        return Ok(());
+    }
 
-    // Play the game.
-    loop {
-        match program.exec() {
-            Signal::Halt => break,
-            // Every wait is one frame refresh.
-            // By then, the ball and paddle position would have changed.
-            Signal::Waiting => {
-                let output = program.output;
-                let mut ball = (0, 0);
-                let mut paddle = (0, 0);
-                for chunk in output.chunks(3) {
-                    match chunk {
-                        &[x, y, tile_id] => match tile_id {
-                            0 => {}               // Empty tile.
-                            1 => {}               // Wall.
-                            2 => {}               // Block.
-                            3 => paddle = (x, y), // Horizontal paddle.
-                            4 => ball = (x, y),   // Ball.
-                            n => println!("score: {}", n),
-                        },
-                        _ => unimplemented!(),
-                    }
-                }
-                program.output = vec![];
-                // Input is left or right.
-                // Paddle must catch up with the ball position.
-                program.set_input(if paddle.0 > ball.0 {
-                    // Tilt to the left
-                    -1
-                } else if paddle.0 < ball.0 {
-                    // Tilt to the right.
-                    1
-                } else {
-                    0
-                });
-                continue;
-            }
+    // Pass `--record out.ppm` to save the frames as `out-0001.ppm` and so on, with
+    // `--record-scale n` pixels per tile and `--record-every n` to keep one frame in n.
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| args.get(i + 1));
+    let count = |name: &str| -> Result<usize, Box<dyn Error>> {
+        match option(name) {
+            None => Ok(1),
+            Some(value) => Ok(value.ok_or(format!("missing value for {}", name))?.parse()?),
         }
-    }
+    };
+    let mut recorder = match option("--record") {
+        None => None,
+        Some(path) => {
+            let path = path.ok_or("missing path for --record")?;
+            let recorder = Recorder::new()
+                .scale(count("--record-scale")?)
+                .every(count("--record-every")?);
+            Some((path, recorder))
+        }
+    };
 
-    for chunk in program.output.chunks(3) {
-        match chunk {
-            &[_, _, tile_id] => match tile_id {
-                0 | 1 | 2 | 3 | 4 => {}
-                n => {
-                    println!("score: {}", n);
-                    assert_eq!(10247, n);
-                }
-            },
-            _ => unimplemented!(),
+    let watch = args.iter().any(|arg| arg == "--watch");
+    let signal = arcade.play(|screen| {
+        if watch {
+            println!("{}", screen.ansi());
         }
+        if let Some((_, recorder)) = recorder.as_mut() {
+            recorder.record(screen);
+        }
+        autopilot(screen)
+    })?;
+    assert_eq!(Signal::Halt, signal);
+    if let Some((path, recorder)) = recorder {
+        recorder.write(Path::new(path))?;
+        println!("recorded {} frames", recorder.frames().len());
     }
 
+    let screen = &arcade.screen;
+    println!("{}", screen.render());
+    assert_eq!(0, screen.blocks());
+    assert_eq!(10247, screen.score);
+
     Ok(())
 }
 
+// Number of blocks on the screen when the game exits, which it does right away without quarters.
+fn part1(input: &str) -> Result<usize, Box<dyn Error>> {
+    let mut arcade = Arcade::new(input);
+    arcade.refresh()?;
+    Ok(arcade.screen.blocks())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn part1_blocks() {
+        let input = include_str!("./input.txt").trim();
+        assert_eq!(207, part1(input).unwrap());
+    }
+
+    #[test]
+    fn autopilot_clears_the_board() {
+        let input = include_str!("./input.txt").trim();
+        let mut arcade = Arcade::free_play(input);
+        assert_eq!(Signal::Halt, arcade.play(autopilot).unwrap());
+        assert_eq!(0, arcade.screen.blocks());
+        assert_eq!(10247, arcade.screen.score);
+    }
+
+    #[test]
+    fn initial_frame_and_one_per_input() {
+        let input = include_str!("./input.txt").trim();
+        let mut arcade = Arcade::free_play(input);
+        let mut recorder = Recorder::new().scale(2);
+        let mut inputs = Vec::new();
+        let signal = arcade
+            .play(|screen| {
+                recorder.record(screen);
+                if inputs.len() == 50 {
+                    return None;
+                }
+                let input = autopilot(screen);
+                inputs.push(input);
+                input
+            })
+            .unwrap();
+        assert_eq!(Signal::Waiting, signal);
+
+        // One initial frame, recorded when the joystick is first asked on the board as the game
+        // draws it, then one frame after each input (the last of those asks goes unanswered).
+        assert_eq!(1 + inputs.len(), recorder.frames().len());
+        let (width, height) = arcade.screen.size();
+        let image = &recorder.frames()[0];
+        assert_eq!((width * 2, height * 2), (image.width, image.height));
+    }
 }
@@ -0,0 +1,157 @@
+use crate::arcade::Screen;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Colors of the tiles, indexed by `Tile as u8`.
+pub const PALETTE: [[u8; 3]; 5] = [
+    // Empty.
+    [0, 0, 0],
+    // Wall.
+    [128, 128, 128],
+    // Block.
+    [230, 180, 40],
+    // Paddle.
+    [40, 200, 220],
+    // Ball.
+    [230, 60, 60],
+];
+
+// A screen captured as one palette index per pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    // Draws every tile as a `scale` by `scale` square.
+    pub fn capture(screen: &Screen, scale: usize) -> Self {
+        let (columns, rows) = screen.size();
+        let (width, height) = (columns * scale, rows * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let tile = screen.tile(((x / scale) as i128, (y / scale) as i128));
+                pixels.push(tile as u8);
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Renders the image as a binary PPM.
+    pub fn ppm(&self) -> Vec<u8> {
+        let mut image = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for &index in &self.pixels {
+            image.extend(PALETTE[index as usize]);
+        }
+        image
+    }
+}
+
+// Captures the screen every time the game waits for the joystick. Call `record` from the
+// joystick of `Arcade::play`, which is asked for a position at exactly those times.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    scale: usize,
+    every: usize,
+    // Number of frames offered to `record`, kept or not.
+    seen: usize,
+    frames: Vec<Image>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            scale: 1,
+            every: 1,
+            seen: 0,
+            frames: vec![],
+        }
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder::default()
+    }
+
+    // Draws every tile `pixels` pixels wide.
+    pub fn scale(mut self, pixels: usize) -> Self {
+        self.scale = pixels.max(1);
+        self
+    }
+
+    // Keeps only the first of every `frames` frames.
+    pub fn every(mut self, frames: usize) -> Self {
+        self.every = frames.max(1);
+        self
+    }
+
+    pub fn record(&mut self, screen: &Screen) {
+        if self.seen.is_multiple_of(self.every) {
+            self.frames.push(Image::capture(screen, self.scale));
+        }
+        self.seen += 1;
+    }
+
+    pub fn frames(&self) -> &[Image] {
+        &self.frames
+    }
+
+    // Writes the frames next to `path` as `out-0001.ppm`, `out-0002.ppm` and so on.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        for (i, frame) in self.frames.iter().enumerate() {
+            let name = format!("{}-{:04}.ppm", stem, i + 1);
+            fs::write(path.with_file_name(name), frame.ppm())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arcade::{Draw, Tile};
+
+    #[test]
+    fn capture() {
+        let mut screen = Screen::default();
+        screen.draw(Draw::Tile {
+            x: 1,
+            y: 0,
+            tile: Tile::Ball,
+        });
+        screen.draw(Draw::Tile {
+            x: 0,
+            y: 1,
+            tile: Tile::Wall,
+        });
+
+        let image = Image::capture(&screen, 2);
+        assert_eq!((4, 4), (image.width, image.height));
+        assert_eq!(vec![0, 0, 4, 4], image.pixels[4..8].to_vec());
+        assert_eq!(vec![1, 1, 0, 0], image.pixels[8..12].to_vec());
+
+        let ppm = image.ppm();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(11 + 16 * 3, ppm.len());
+        assert_eq!(&PALETTE[Tile::Ball as usize], &ppm[11 + 2 * 3..11 + 3 * 3]);
+    }
+
+    #[test]
+    fn every() {
+        let mut recorder = Recorder::new().every(3);
+        for _ in 0..7 {
+            recorder.record(&Screen::default());
+        }
+        // Frames 0, 3 and 6.
+        assert_eq!(3, recorder.frames().len());
+    }
+}
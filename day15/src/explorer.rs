@@ -0,0 +1,101 @@
+use crate::map::{Cell, Map};
+use intcode::{Intcode, Signal};
+use std::error::Error;
+
+// Movement commands: north, south, west and east.
+const MOVES: [i128; 4] = [1, 2, 3, 4];
+
+// The command that undoes a move.
+pub fn backtrack(i: i128) -> i128 {
+    match i {
+        1 => 2,
+        2 => 1,
+        3 => 4,
+        4 => 3,
+        n => unimplemented!("n: {}", n),
+    }
+}
+
+fn neighbour(pos: (i32, i32), i: i128) -> (i32, i32) {
+    let (x, y) = pos;
+    match i {
+        1 => (x, y - 1),
+        2 => (x, y + 1),
+        3 => (x - 1, y),
+        4 => (x + 1, y),
+        n => unimplemented!("n: {}", n),
+    }
+}
+
+// Drives a single repair droid through the whole ship, depth first. The droid steps into every
+// unknown neighbour it can reach and walks back with `backtrack` once there is none left around
+// it, so every cell reachable from the start ends up on the map.
+pub struct Explorer {
+    droid: Intcode,
+    pos: (i32, i32),
+    pub map: Map,
+    // Number of move commands given to the droid.
+    pub moves: usize,
+}
+
+impl Explorer {
+    pub fn new(droid: Intcode) -> Self {
+        Explorer {
+            droid,
+            pos: (0, 0),
+            map: Map::new((0, 0)),
+            moves: 0,
+        }
+    }
+
+    // Sends one move command and records what the droid reports. Returns whether it moved.
+    fn send(&mut self, i: i128) -> Result<bool, Box<dyn Error>> {
+        self.droid.input.push(i);
+        self.moves += 1;
+        if self.droid.exec() == Signal::Halt {
+            return Err("the droid halted".into());
+        }
+        let status = self
+            .droid
+            .output
+            .pop()
+            .ok_or("the droid did not report a status")?;
+
+        let next = neighbour(self.pos, i);
+        let cell = match status {
+            0 => Cell::Wall,
+            1 => Cell::Open,
+            2 => Cell::Oxygen,
+            n => return Err(format!("unknown status {}", n).into()),
+        };
+        self.map.cells.insert(next, cell);
+        if cell != Cell::Wall {
+            self.pos = next;
+        }
+        Ok(cell != Cell::Wall)
+    }
+
+    pub fn explore(mut self) -> Result<Map, Box<dyn Error>> {
+        // The moves that led from the start to the current position.
+        let mut path: Vec<i128> = vec![];
+        loop {
+            let unknown = MOVES
+                .into_iter()
+                .find(|&i| !self.map.cells.contains_key(&neighbour(self.pos, i)));
+            match (unknown, path.last()) {
+                (Some(i), _) => {
+                    if self.send(i)? {
+                        path.push(i);
+                    }
+                }
+                (None, Some(&i)) => {
+                    if !self.send(backtrack(i))? {
+                        return Err(format!("cannot backtrack from {:?}", self.pos).into());
+                    }
+                    path.pop();
+                }
+                (None, None) => return Ok(self.map),
+            }
+        }
+    }
+}
@@ -1,77 +1,72 @@
-use intcode::{Intcode, Signal};
+use intcode::Intcode;
+use std::env;
 use std::error::Error;
 use std::fs;
 
+mod explorer;
+mod map;
+mod oxygen;
+use explorer::*;
+use map::*;
+use oxygen::*;
+
 fn main() -> Result<(), Box<dyn Error>> {
     let input = fs::read_to_string("./src/input.txt")?;
     let input = input.trim();
 
-    let drone = Intcode::from(input.to_string());
+    // Map the whole ship first, then answer both parts from the map. Pass `--map <path>` to save
+    // the map as text, and `--load <path>` to answer from a saved map instead of exploring.
+    let args = env::args().collect::<Vec<String>>();
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .map(|i| args.get(i + 1).ok_or(format!("missing value for {}", name)))
+    };
+    let map = match option("--load") {
+        Some(path) => Map::parse(&fs::read_to_string(path?)?)?,
+        None => explore(input)?,
+    };
+    println!("{}", map.render());
+    if let Some(path) = option("--map") {
+        fs::write(path?, map.render() + "\n")?;
+    }
 
-    let (steps, drone) = find_shortest_path(drone);
+    let steps = map.shortest_path().ok_or("no path to the oxygen system")?;
     assert_eq!(226, steps);
 
-    let (steps, _) = find_shortest_path(drone);
-    assert_eq!(343, steps);
+    // Part 2: oxygen takes a minute to reach each next cell, so the ship is full once it
+    // reaches the cell farthest from the oxygen system.
+    let minutes = map.fill_time().ok_or("no oxygen system")?;
+    assert_eq!(342, minutes);
+
+    // Pass `--minute n` to see how far the oxygen got after n minutes.
+    let spread = Spread::new(&map, map.oxygen().unwrap());
+    assert_eq!(minutes, spread.minutes());
+    if let Some(minute) = option("--minute") {
+        println!("{}", spread.render(minute?.parse()?));
+    }
+    println!("{}", spread.summary(3));
 
     Ok(())
 }
 
-fn backtrack(i: i32) -> i32 {
-    match i {
-        1 => 2,
-        2 => 1,
-        3 => 4,
-        4 => 3,
-        n => unimplemented!("n: {}", n),
-    }
+fn explore(input: &str) -> Result<Map, Box<dyn Error>> {
+    Explorer::new(Intcode::from(input.to_string())).explore()
 }
 
-fn find_shortest_path(drone: Intcode) -> (i32, Intcode) {
-    let mut depth = 0;
-
-    // Do a breadth-first search by deploying the drones to all possible directions.
-    let mut drones: Vec<(i32, Intcode)> = vec![(-1, drone.clone())];
-
-    loop {
-        // Take drones for the current batch.
-        let programs = drones.clone();
-        drones.clear();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Deploy to all directions.
-        for (prev, program) in programs {
-            for i in 1..=4 {
-                // Don't let the drone move backward, only move forward.
-                if backtrack(i) == prev {
-                    continue;
-                }
-                let mut program = program.clone();
-                program.set_input(i as i128);
-                match program.exec() {
-                    Signal::Waiting => {
-                        let status = program.output.pop().unwrap();
-                        match status {
-                            0 => {
-                                //println!("hit roadblock, {:?}", drones.len());
-                            }
-                            1 => {
-                                //println!("can move");
-                                drones.push((i as i32, program));
-                            }
-                            2 => return (depth + 1, program),
-                            _ => unimplemented!(),
-                        }
-                    }
-                    Signal::Halt => (),
-                }
-            }
-        }
+    #[test]
+    fn ship() {
+        let input = include_str!("./input.txt").trim();
+        let map = explore(input).unwrap();
+        assert_eq!(Some(226), map.shortest_path());
+        assert_eq!(Some(342), map.fill_time());
 
-        depth += 1;
-        // Part 2 is easier. Start from the oxygen tank, and keep walking until there are no more
-        // paths.
-        if drones.len() == 0 {
-            return (depth, drone);
-        }
+        // The exported text reads back into the same map, shifted to start at (0, 0).
+        let text = map.render();
+        assert_eq!(text, Map::parse(&text).unwrap().render());
     }
 }
@@ -0,0 +1,152 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+// The cells of the ship that have been seen, with y growing southwards. Cells missing from the
+// map are unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    pub cells: HashMap<(i32, i32), Cell>,
+    // Where the droid started.
+    pub start: (i32, i32),
+}
+
+impl Map {
+    pub fn new(start: (i32, i32)) -> Self {
+        Map {
+            cells: HashMap::from([(start, Cell::Open)]),
+            start,
+        }
+    }
+
+    pub fn oxygen(&self) -> Option<(i32, i32)> {
+        self.cells
+            .iter()
+            .find(|(_, &cell)| cell == Cell::Oxygen)
+            .map(|(&pos, _)| pos)
+    }
+
+    // Neighbours of `pos` that are known not to be walls.
+    pub fn open_neighbours(&self, pos: (i32, i32)) -> Vec<(i32, i32)> {
+        let (x, y) = pos;
+        [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+            .into_iter()
+            .filter(|pos| matches!(self.cells.get(pos), Some(Cell::Open | Cell::Oxygen)))
+            .collect()
+    }
+
+    // Number of moves from `from` to every cell it can reach through known cells.
+    pub fn distances(&self, from: (i32, i32)) -> HashMap<(i32, i32), usize> {
+        let mut distances = HashMap::from([(from, 0)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[&pos];
+            for next in self.open_neighbours(pos) {
+                if let Entry::Vacant(entry) = distances.entry(next) {
+                    entry.insert(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    // Fewest moves from the start to the oxygen system.
+    pub fn shortest_path(&self) -> Option<usize> {
+        self.distances(self.start).get(&self.oxygen()?).copied()
+    }
+
+    // Minutes until oxygen spreading from the oxygen system reaches every open cell.
+    pub fn fill_time(&self) -> Option<usize> {
+        self.distances(self.oxygen()?).into_values().max()
+    }
+
+    // Draws walls as `#`, open cells as `.`, the oxygen system as `O`, the start as `S` and
+    // unknown cells as spaces.
+    pub fn render(&self) -> String {
+        let xs = self.cells.keys().map(|pos| pos.0);
+        let ys = self.cells.keys().map(|pos| pos.1);
+        let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+        let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+
+        (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| match self.cells.get(&(x, y)) {
+                        Some(_) if (x, y) == self.start => 'S',
+                        Some(Cell::Wall) => '#',
+                        Some(Cell::Open) => '.',
+                        Some(Cell::Oxygen) => 'O',
+                        None => ' ',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Reads a map drawn by `render`, placing its top left corner at (0, 0). The start is at
+    // (0, 0) if the drawing has no `S`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut map = Map {
+            cells: HashMap::new(),
+            start: (0, 0),
+        };
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as i32, y as i32);
+                let cell = match c {
+                    '#' => Cell::Wall,
+                    '.' => Cell::Open,
+                    'O' => Cell::Oxygen,
+                    'S' => {
+                        map.start = pos;
+                        Cell::Open
+                    }
+                    ' ' => continue,
+                    c => return Err(format!("unknown cell {:?} at {:?}", c, pos)),
+                };
+                map.cells.insert(pos, cell);
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of part 2.
+    const EXAMPLE: &str = " ##
+#..##
+#.#..#
+#.O.#
+ ###";
+
+    #[test]
+    fn example() {
+        let map = Map::parse(EXAMPLE).unwrap();
+        assert_eq!(Some((2, 3)), map.oxygen());
+        assert_eq!(Some(4), map.fill_time());
+        assert_eq!(Some(4), map.distances((2, 3)).get(&(2, 1)).copied());
+    }
+
+    #[test]
+    fn render() {
+        let mut map = Map::parse(EXAMPLE).unwrap();
+        map.start = (1, 1);
+        let text = map.render();
+        assert_eq!(" ##\n#S.##\n#.#..#\n#.O.#\n ###", text);
+        assert_eq!(map, Map::parse(&text).unwrap());
+        assert_eq!(Some(3), map.shortest_path());
+    }
+}
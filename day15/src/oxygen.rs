@@ -0,0 +1,95 @@
+use crate::map::{Cell, Map};
+use std::collections::HashMap;
+
+// How oxygen spreads from a tank through the open cells of a map, one cell further every minute.
+pub struct Spread {
+    map: Map,
+    // Cells oxygen reaches at each minute. Minute 0 is the tank alone.
+    pub frontiers: Vec<Vec<(i32, i32)>>,
+}
+
+impl Spread {
+    pub fn new(map: &Map, tank: (i32, i32)) -> Self {
+        let mut frontiers = vec![];
+        for (pos, minute) in map.distances(tank) {
+            if frontiers.len() <= minute {
+                frontiers.resize(minute + 1, vec![]);
+            }
+            frontiers[minute].push(pos);
+        }
+        for frontier in frontiers.iter_mut() {
+            frontier.sort();
+        }
+        Spread {
+            map: map.clone(),
+            frontiers,
+        }
+    }
+
+    // Minutes until every reachable cell has oxygen.
+    pub fn minutes(&self) -> usize {
+        self.frontiers.len().saturating_sub(1)
+    }
+
+    // Minute at which oxygen reaches each cell.
+    pub fn filled(&self) -> HashMap<(i32, i32), usize> {
+        self.frontiers
+            .iter()
+            .enumerate()
+            .flat_map(|(minute, frontier)| frontier.iter().map(move |&pos| (pos, minute)))
+            .collect()
+    }
+
+    // The map as `Map::render` draws it, with every cell that has oxygen after `minute` minutes
+    // drawn as `O`.
+    pub fn render(&self, minute: usize) -> String {
+        let mut map = self.map.clone();
+        for frontier in self.frontiers.iter().take(minute + 1) {
+            for &pos in frontier {
+                map.cells.insert(pos, Cell::Oxygen);
+            }
+        }
+        map.render()
+    }
+
+    // Dead ends, the open cells with a single open neighbour, with the minute oxygen reaches
+    // them. The last ones to fill come first.
+    pub fn dead_ends(&self) -> Vec<((i32, i32), usize)> {
+        let mut dead_ends = self
+            .filled()
+            .into_iter()
+            .filter(|&(pos, minute)| minute > 0 && self.map.open_neighbours(pos).len() == 1)
+            .collect::<Vec<((i32, i32), usize)>>();
+        dead_ends.sort_by_key(|&(pos, minute)| (usize::MAX - minute, pos));
+        dead_ends
+    }
+
+    // One line for each of the `count` dead ends that fill last.
+    pub fn summary(&self, count: usize) -> String {
+        self.dead_ends()
+            .iter()
+            .take(count)
+            .map(|((x, y), minute)| format!("minute {}: dead end at ({}, {})", minute, x, y))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let map = Map::parse(" ##\n#..##\n#.#..#\n#.O.#\n ###").unwrap();
+        let spread = Spread::new(&map, map.oxygen().unwrap());
+
+        assert_eq!(4, spread.minutes());
+        assert_eq!(vec![(1, 3), (3, 3)], spread.frontiers[1]);
+        assert_eq!(" ##\n#..##\n#O#O.#\n#OOO#\n ###", spread.render(2));
+        assert_eq!(map.render(), spread.render(0));
+
+        assert_eq!(vec![((2, 1), 4), ((4, 2), 3)], spread.dead_ends());
+        assert_eq!("minute 4: dead end at (2, 1)", spread.summary(1));
+    }
+}
@@ -0,0 +1,210 @@
+use super::Direction;
+
+// A path split into movement functions, and the main routine that calls them in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decomposition {
+    // Indices into `functions`, 0 being A.
+    pub main: Vec<usize>,
+    pub functions: Vec<Vec<Direction>>,
+}
+
+impl Decomposition {
+    // Main routine in the robot's syntax, e.g. A,B,A,C.
+    pub fn main_routine(&self) -> String {
+        self.main
+            .iter()
+            .map(|&i| ((b'A' + i as u8) as char).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // Movement function in the robot's syntax, e.g. R,8,L,10.
+    pub fn function(&self, i: usize) -> String {
+        routine(&self.functions[i])
+    }
+
+    // The moves the main routine makes.
+    pub fn path(&self) -> Vec<Direction> {
+        self.main
+            .iter()
+            .flat_map(|&i| self.functions[i].iter().copied())
+            .collect()
+    }
+}
+
+pub fn routine(moves: &[Direction]) -> String {
+    use Direction::*;
+    moves
+        .iter()
+        .map(|mv| match mv {
+            LEFT(n) => format!("L,{}", n),
+            RIGHT(n) => format!("R,{}", n),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// Length of the routine in ASCII, without the newline that ends it.
+fn routine_len(moves: &[Direction]) -> usize {
+    moves
+        .iter()
+        .map(|mv| 3 + mv.steps().to_string().len())
+        .sum::<usize>()
+        .saturating_sub(1)
+}
+
+// Splits a path into at most `functions` movement functions, where neither the main routine nor
+// any function is longer than `max_len` characters.
+pub struct Compressor {
+    functions: usize,
+    max_len: usize,
+}
+
+impl Compressor {
+    pub fn new(functions: usize) -> Self {
+        Compressor {
+            functions,
+            max_len: 20,
+        }
+    }
+
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    // Every valid decomposition of the path. Functions are numbered in the order the main
+    // routine first calls them, so no two decompositions differ only by their names.
+    pub fn solve(&self, path: &[Direction]) -> Vec<Decomposition> {
+        let mut found = vec![];
+        let mut decomposition = Decomposition {
+            main: vec![],
+            functions: vec![],
+        };
+        self.search(path, &mut decomposition, &mut found);
+        found
+    }
+
+    fn search(
+        &self,
+        path: &[Direction],
+        current: &mut Decomposition,
+        found: &mut Vec<Decomposition>,
+    ) {
+        if path.is_empty() {
+            found.push(current.clone());
+            return;
+        }
+        // One more call must still fit in the main routine: a letter, and a comma before it.
+        if current.main.len() * 2 + 1 > self.max_len {
+            return;
+        }
+
+        // Call a function that the path continues with.
+        for i in 0..current.functions.len() {
+            let function = &current.functions[i];
+            if path.starts_with(function) {
+                let rest = &path[function.len()..];
+                current.main.push(i);
+                self.search(rest, current, found);
+                current.main.pop();
+            }
+        }
+
+        // Or define a new one from the start of the path, as long as it fits.
+        if current.functions.len() == self.functions {
+            return;
+        }
+        for len in 1..=path.len() {
+            let function = &path[..len];
+            if routine_len(function) > self.max_len {
+                break;
+            }
+            if current.functions.iter().any(|f| f == function) {
+                continue;
+            }
+            current.functions.push(function.to_vec());
+            current.main.push(current.functions.len() - 1);
+            self.search(&path[len..], current, found);
+            current.main.pop();
+            current.functions.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn example() {
+        // The path of the example in part 2.
+        let path = [
+            RIGHT(8),
+            RIGHT(8),
+            RIGHT(4),
+            RIGHT(4),
+            RIGHT(8),
+            LEFT(6),
+            LEFT(2),
+            RIGHT(4),
+            RIGHT(4),
+            RIGHT(8),
+            RIGHT(8),
+            RIGHT(8),
+            LEFT(6),
+            LEFT(2),
+        ];
+        let found = Compressor::new(3).solve(&path);
+        assert!(!found.is_empty());
+        assert!(found.iter().all(|d| d.path() == path));
+        assert!(found.iter().all(|d| d.functions.len() <= 3));
+        assert!(found
+            .iter()
+            .all(|d| (0..d.functions.len()).all(|i| d.function(i).len() <= 20)));
+
+        // The split given in the puzzle is one of them.
+        let expected = Decomposition {
+            main: vec![0, 1, 2, 1, 0, 2],
+            functions: vec![
+                vec![RIGHT(8), RIGHT(8)],
+                vec![RIGHT(4), RIGHT(4), RIGHT(8)],
+                vec![LEFT(6), LEFT(2)],
+            ],
+        };
+        assert!(found.contains(&expected));
+        assert_eq!("A,B,C,B,A,C", expected.main_routine());
+        assert_eq!("R,4,R,4,R,8", expected.function(1));
+    }
+
+    #[test]
+    fn lengths() {
+        // "R,10,L,12" is 9 characters, so two moves fit in 10 but not in 8.
+        let path = [RIGHT(10), LEFT(12), RIGHT(10), LEFT(12)];
+        assert_eq!(9, routine_len(&path[..2]));
+
+        let found = Compressor::new(1).max_len(10).solve(&path);
+        assert_eq!(
+            vec![Decomposition {
+                main: vec![0, 0],
+                functions: vec![vec![RIGHT(10), LEFT(12)]],
+            }],
+            found
+        );
+        assert!(Compressor::new(1).max_len(8).solve(&path).is_empty());
+
+        // Three calls need "A,A,A", 5 characters.
+        let path = [RIGHT(1), RIGHT(1), RIGHT(1)];
+        assert_eq!(
+            vec![vec![0, 0, 0]],
+            Compressor::new(1)
+                .max_len(5)
+                .solve(&path)
+                .into_iter()
+                .map(|d| d.main)
+                .collect::<Vec<Vec<usize>>>()
+        );
+        assert!(Compressor::new(1).max_len(4).solve(&path).is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use super::Direction;
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub fn left(self) -> Self {
+        use Heading::*;
+        match self {
+            North => West,
+            West => South,
+            South => East,
+            East => North,
+        }
+    }
+
+    pub fn right(self) -> Self {
+        self.left().left().left()
+    }
+
+    // Where a step leads, with y growing downwards as in the camera image.
+    pub fn step(self, (x, y): (i32, i32)) -> (i32, i32) {
+        use Heading::*;
+        match self {
+            North => (x, y - 1),
+            East => (x + 1, y),
+            South => (x, y + 1),
+            West => (x - 1, y),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Pose {
+    pub pos: (i32, i32),
+    pub heading: Heading,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Command {
+    Left,
+    Right,
+    Forward(usize),
+}
+
+// The scaffold seen by the camera, and where the robot stands on it.
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    cells: HashSet<(i32, i32)>,
+    pub robot: Pose,
+}
+
+impl Scaffold {
+    // Reads the camera image: `#` for scaffold, `.` for open space and `^`, `>`, `v` or `<` for
+    // the robot, which stands on scaffold.
+    pub fn parse(image: &str) -> Result<Self, String> {
+        let mut cells = HashSet::new();
+        let mut robot = None;
+        for (y, line) in image.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as i32, y as i32);
+                let heading = match c {
+                    '.' => continue,
+                    '#' => None,
+                    '^' => Some(Heading::North),
+                    '>' => Some(Heading::East),
+                    'v' => Some(Heading::South),
+                    '<' => Some(Heading::West),
+                    c => return Err(format!("unknown pixel {:?} at {:?}", c, pos)),
+                };
+                if let Some(heading) = heading {
+                    robot = Some(Pose { pos, heading });
+                }
+                cells.insert(pos);
+            }
+        }
+        let robot = robot.ok_or("no robot in the image")?;
+        Ok(Scaffold { cells, robot })
+    }
+
+    // Reads the image as the camera outputs it, up to the first blank line.
+    pub fn from_output(output: &[i128]) -> Result<Self, String> {
+        let text = output.iter().map(|&c| c as u8 as char).collect::<String>();
+        Scaffold::parse(text.split("\n\n").next().unwrap_or_default())
+    }
+
+    pub fn is_scaffold(&self, pos: (i32, i32)) -> bool {
+        self.cells.contains(&pos)
+    }
+
+    // Scaffold cells with scaffold on all four sides, in reading order.
+    pub fn intersections(&self) -> Vec<(i32, i32)> {
+        let mut intersections = self
+            .cells
+            .iter()
+            .copied()
+            .filter(|&pos| {
+                [Heading::North, Heading::East, Heading::South, Heading::West]
+                    .iter()
+                    .all(|heading| self.is_scaffold(heading.step(pos)))
+            })
+            .collect::<Vec<(i32, i32)>>();
+        intersections.sort_by_key(|&(x, y)| (y, x));
+        intersections
+    }
+
+    // Sum of the alignment parameters, x * y, of the intersections.
+    pub fn alignment(&self) -> i32 {
+        self.intersections().iter().map(|(x, y)| x * y).sum()
+    }
+
+    // Walks the robot straight across intersections and turns only where the scaffold does,
+    // until it reaches the end. The robot turns around if it starts facing away from the only
+    // scaffold next to it. Fails if the walk leaves some scaffold unvisited.
+    pub fn commands(&self) -> Result<Vec<Command>, String> {
+        let mut commands = vec![];
+        let mut visited = HashSet::from([self.robot.pos]);
+        let Pose {
+            mut pos,
+            mut heading,
+        } = self.robot;
+        loop {
+            let mut forward = 0;
+            while self.is_scaffold(heading.step(pos)) {
+                pos = heading.step(pos);
+                visited.insert(pos);
+                forward += 1;
+            }
+            if forward > 0 {
+                commands.push(Command::Forward(forward));
+            }
+
+            if self.is_scaffold(heading.left().step(pos)) {
+                heading = heading.left();
+                commands.push(Command::Left);
+            } else if self.is_scaffold(heading.right().step(pos)) {
+                heading = heading.right();
+                commands.push(Command::Right);
+            } else {
+                // The way back is the way the robot came, unless it has not moved yet.
+                let behind = heading.right().right();
+                let back = behind.step(pos);
+                if !self.is_scaffold(back) || visited.contains(&back) {
+                    break;
+                }
+                heading = behind;
+                commands.extend([Command::Right, Command::Right]);
+            }
+        }
+
+        let unvisited = self.cells.len() - visited.len();
+        if unvisited > 0 {
+            return Err(format!("{} scaffold cells are not on the path", unvisited));
+        }
+        Ok(commands)
+    }
+
+    // The commands as turns each followed by moving forward, as `Compressor` takes them. A path
+    // that starts straight ahead or with a U-turn has no such form.
+    pub fn moves(&self) -> Result<Vec<Direction>, String> {
+        let commands = self.commands()?;
+        if let Some(Command::Forward(n)) = commands.first() {
+            return Err(format!(
+                "the path starts {} steps straight ahead, before any turn",
+                n
+            ));
+        }
+        commands
+            .chunks(2)
+            .map(|pair| match *pair {
+                [Command::Left, Command::Forward(n)] => Ok(Direction::LEFT(n)),
+                [Command::Right, Command::Forward(n)] => Ok(Direction::RIGHT(n)),
+                [Command::Left | Command::Right, Command::Left | Command::Right] => {
+                    Err("the path starts with a U-turn".to_string())
+                }
+                _ => Err(format!("expected a turn and a move, got {:?}", pair)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn intersections() {
+        let image = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..";
+        let scaffold = Scaffold::parse(image).unwrap();
+        assert_eq!(
+            vec![(2, 2), (2, 4), (6, 4), (10, 4)],
+            scaffold.intersections()
+        );
+        assert_eq!(76, scaffold.alignment());
+        assert_eq!(
+            Pose {
+                pos: (10, 6),
+                heading: Heading::North
+            },
+            scaffold.robot
+        );
+    }
+
+    #[test]
+    fn moves() {
+        let image = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......";
+        let scaffold = Scaffold::parse(image).unwrap();
+        assert_eq!(
+            vec![
+                RIGHT(8),
+                RIGHT(8),
+                RIGHT(4),
+                RIGHT(4),
+                RIGHT(8),
+                LEFT(6),
+                LEFT(2),
+                RIGHT(4),
+                RIGHT(4),
+                RIGHT(8),
+                RIGHT(8),
+                RIGHT(8),
+                LEFT(6),
+                LEFT(2),
+            ],
+            scaffold.moves().unwrap()
+        );
+    }
+
+    #[test]
+    fn straight_starts() {
+        // Facing away from the scaffold, the robot turns around first.
+        let scaffold = Scaffold::parse("#\n#\nv").unwrap();
+        assert_eq!(
+            Ok(vec![Command::Right, Command::Right, Command::Forward(2)]),
+            scaffold.commands()
+        );
+        assert_eq!(
+            Err("the path starts with a U-turn".to_string()),
+            scaffold.moves()
+        );
+
+        let scaffold = Scaffold::parse("#\n#\n^").unwrap();
+        assert_eq!(Ok(vec![Command::Forward(2)]), scaffold.commands());
+        assert_eq!(
+            Err("the path starts 2 steps straight ahead, before any turn".to_string()),
+            scaffold.moves()
+        );
+    }
+
+    #[test]
+    fn unvisited() {
+        // The robot turns left at the end and never sees the cell on its right.
+        let scaffold = Scaffold::parse("#^#").unwrap();
+        assert_eq!(
+            Err("1 scaffold cells are not on the path".to_string()),
+            scaffold.commands()
+        );
+    }
+}
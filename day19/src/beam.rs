@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+// How far right of the last traced beam cells an empty row is searched, per row since then.
+const SEARCH: i128 = 10;
+
+// Rows `fit` looks at before giving up.
+const LIMIT: i128 = 100_000;
+
+// The tractor beam as a cone from the emitter at (0, 0), with y growing away from it. Each row of
+// the beam is one run of cells whose edges only ever move right, so a row is traced from the
+// edges of the row above with a handful of queries. Every query is cached.
+pub struct Beam<F> {
+    query: F,
+    cache: HashMap<(i128, i128), bool>,
+    // First and last x of the beam on each row traced so far, or None for empty rows.
+    rows: Vec<Option<(i128, i128)>>,
+}
+
+impl<F> Beam<F>
+where
+    F: Fn(i128, i128) -> bool,
+{
+    // `query` tells whether a point is pulled by the beam.
+    pub fn new(query: F) -> Self {
+        Beam {
+            query,
+            cache: HashMap::new(),
+            rows: vec![],
+        }
+    }
+
+    pub fn get(&mut self, x: i128, y: i128) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        let query = &self.query;
+        *self.cache.entry((x, y)).or_insert_with(|| query(x, y))
+    }
+
+    // Number of distinct points queried so far.
+    pub fn queries(&self) -> usize {
+        self.cache.len()
+    }
+
+    // First and last x of the beam on row `y`, or None if the beam does not reach it.
+    pub fn row(&mut self, y: i128) -> Option<(i128, i128)> {
+        if y < 0 {
+            return None;
+        }
+        while self.rows.len() as i128 <= y {
+            let row = self.trace(self.rows.len() as i128);
+            self.rows.push(row);
+        }
+        self.rows[y as usize]
+    }
+
+    fn trace(&mut self, y: i128) -> Option<(i128, i128)> {
+        let previous = (0..y)
+            .rev()
+            .find_map(|row| self.rows[row as usize].map(|edges| (row, edges)));
+        let (start, end, limit) = match previous {
+            Some((row, (start, end))) => (start, end, end + SEARCH * (y - row)),
+            None => (0, 0, SEARCH * (y + 1)),
+        };
+
+        let mut first = start;
+        while !self.get(first, y) {
+            if first >= limit {
+                return None;
+            }
+            first += 1;
+        }
+
+        let mut last = first;
+        if end > first && self.get(end, y) {
+            last = end;
+        }
+        while self.get(last + 1, y) {
+            last += 1;
+        }
+        Some((first, last))
+    }
+
+    // Top left corner of the first `width` x `height` area that fits entirely in the beam,
+    // closest to the emitter. Nothing fits an empty area.
+    pub fn fit(&mut self, width: i128, height: i128) -> Option<(i128, i128)> {
+        if width < 1 || height < 1 {
+            return None;
+        }
+        for bottom in height - 1..LIMIT {
+            // Leftmost position of the area on its bottom row, which the top row must reach.
+            let Some((x, _)) = self.row(bottom) else {
+                continue;
+            };
+            let top = bottom - height + 1;
+            if let Some((start, end)) = self.row(top) {
+                if start <= x && x + width - 1 <= end {
+                    return Some((x, top));
+                }
+            }
+        }
+        None
+    }
+
+    // Whether each point of the `width` x `height` area at the emitter is in the beam, row by
+    // row, from the traced edges.
+    pub fn scan(&mut self, width: i128, height: i128) -> Vec<Vec<bool>> {
+        (0..height)
+            .map(|y| {
+                let row = self.row(y);
+                (0..width)
+                    .map(|x| row.is_some_and(|(start, end)| start <= x && x <= end))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Renders the scan with `#` for the beam and `.` elsewhere.
+    pub fn render(&mut self, width: i128, height: i128) -> String {
+        self.scan(width, height)
+            .iter()
+            .map(|row| row.iter().map(|&b| if b { '#' } else { '.' }).collect())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Renders the scan as a binary PPM with every point `scale` pixels wide.
+    pub fn ppm(&mut self, width: i128, height: i128, scale: usize) -> Vec<u8> {
+        let scan = self.scan(width, height);
+        let scale = scale.max(1);
+        let (width, height) = (width as usize * scale, height as usize * scale);
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for y in 0..height {
+            for x in 0..width {
+                image.extend(match scan[y / scale][x / scale] {
+                    true => [80, 160, 255],
+                    false => [0, 0, 0],
+                });
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A beam covering y / 2 <= x <= y.
+    fn cone(x: i128, y: i128) -> bool {
+        y <= 2 * x && x <= y
+    }
+
+    // The first fit by trying every point of every row.
+    fn brute_force(width: i128, height: i128) -> (i128, i128) {
+        (0..)
+            .flat_map(|y| (0..=y).map(move |x| (x, y)))
+            .find(|&(x, y)| (0..width).all(|i| (0..height).all(|j| cone(x + i, y + j))))
+            .unwrap()
+    }
+
+    #[test]
+    fn rows() {
+        let mut beam = Beam::new(cone);
+        assert_eq!(Some((0, 0)), beam.row(0));
+        assert_eq!(Some((5, 9)), beam.row(9));
+        assert_eq!(None, beam.row(-1));
+        assert_eq!("#...\n.#..\n.##.\n..##", beam.render(4, 4));
+    }
+
+    #[test]
+    fn fit() {
+        let mut beam = Beam::new(cone);
+        assert_eq!(Some((2, 3)), beam.fit(2, 2));
+        for (width, height) in [(1, 1), (3, 2), (2, 5), (10, 10), (7, 3)] {
+            assert_eq!(
+                Some(brute_force(width, height)),
+                beam.fit(width, height),
+                "{} x {}",
+                width,
+                height
+            );
+        }
+        // Far fewer queries than there are points up to the last fit.
+        assert!(beam.queries() < 200, "{} queries", beam.queries());
+
+        assert_eq!(None, beam.fit(2, 0));
+        assert_eq!(None, beam.fit(0, 2));
+        assert_eq!(None, beam.fit(-1, -1));
+    }
+
+    #[test]
+    fn empty_rows() {
+        // Nothing but the emitter until row 3.
+        let mut beam = Beam::new(|x, y| (x, y) == (0, 0) || (y >= 3 && cone(x, y)));
+        assert_eq!(None, beam.row(2));
+        assert_eq!(Some((2, 3)), beam.row(3));
+
+        let image = beam.ppm(4, 4, 2);
+        assert!(image.starts_with(b"P6\n8 8\n255\n"));
+        assert_eq!(11 + 8 * 8 * 3, image.len());
+    }
+}
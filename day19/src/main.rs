@@ -1,95 +1,123 @@
+use intcode::batch::Batch;
+use intcode::coverage::Coverage;
 use intcode::Intcode;
 
+use std::env;
 use std::error::Error;
 use std::fs;
 
+mod beam;
+use beam::*;
+
 const MAX: i128 = 50;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let input = fs::read_to_string("./src/input.txt")?;
-    let input = input.trim();
 
-    assert_eq!(226, area(input, MAX));
-    assert_eq!(7900946, part2(input));
+    // Prints which parts of the drone program the part 1 scan exercises.
+    if env::args().any(|arg| arg == "--coverage") {
+        let codes = Intcode::parse(input.trim());
+        print!("{}", coverage(input.trim(), MAX).annotate(&codes));
+        return Ok(());
+    }
+
+    let beam = Batch::new(input.trim()).memoize();
+
+    assert_eq!(226, area(&beam, MAX));
+    let mut ship = Beam::new(|x, y| beam.query(vec![x, y]) == [1]);
+    let position = part2(&mut ship).ok_or("the ship does not fit in the beam")?;
+    println!(
+        "ship fits at ({}, {}) after {} queries",
+        position / 10_000,
+        position % 10_000,
+        ship.queries()
+    );
+    assert_eq!(7900946, position);
+
+    // Pass `--image <path>` to save the part 1 scan as a PPM.
+    let mut traced = Beam::new(|x, y| beam.query(vec![x, y]) == [1]);
+    println!("{}", traced.render(MAX, MAX));
+    let args = env::args().collect::<Vec<String>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--image") {
+        let path = args.get(i + 1).ok_or("missing path for --image")?;
+        fs::write(path, traced.ppm(MAX, MAX, 8))?;
+    }
 
     Ok(())
 }
 
-fn area(input: &str, rng: i128) -> i128 {
-    (0..rng)
-        .flat_map(|i| {
-            (0..rng)
-                .flat_map(|j| {
-                    let mut program = Intcode::new(&input, vec![i, j]);
-                    program.run_until_halt()
-                })
-                .collect::<Vec<i128>>()
-        })
-        .sum::<i128>()
+fn area(beam: &Batch, rng: i128) -> i128 {
+    let points = (0..rng)
+        .flat_map(|i| (0..rng).map(move |j| vec![i, j]))
+        .collect::<Vec<Vec<i128>>>();
+    beam.run(&points).into_iter().flatten().sum::<i128>()
 }
 
-fn check_area(input: &str, pos: (i128, i128), rng: i128) -> bool {
-    let mut area = 0;
-
+// Coverage of the drone program over every query of a `rng` x `rng` scan.
+fn coverage(input: &str, rng: i128) -> Coverage {
+    let image = Intcode::from(input.to_string());
+    let mut coverage = Coverage::new();
     for i in 0..rng {
         for j in 0..rng {
-            let mut program = Intcode::new(&input, vec![pos.0 + i, pos.1 + j]);
-            let output = program.run_until_halt();
-            if output[0] != 1 {
-                return false;
-            }
-            area += 1;
+            let mut program = image.fork();
+            program.with_input(vec![i, j]);
+            coverage.exec(&mut program);
         }
     }
-    area == rng * rng
+    coverage
 }
 
-fn part2(input: &str) -> i128 {
-    let mut x = 100;
-    let mut y = 100;
-
-    let check_corners = |x, y| {
-        let mut program = Intcode::new(&input, vec![x - 99, y + 99]);
-        let output = program.run_until_halt();
-        if output[0] == 1 && check_area(input, (x - 99, y), 100) {
-            Some((x - 99, y))
-        } else {
-            None
-        }
-    };
-    let mut result = 0;
-
-    loop {
-        let mut program = Intcode::new(&input, vec![x, y]);
-        let output = program.run_until_halt();
-        match output[0] {
-            0 => {
-                // As long as y is 0, keep moving down.
-                y += 1;
-                continue;
-            }
-            1 => {
-                // Hits y.
-                match check_corners(x, y) {
-                    Some((x, y)) => {
-                        result = x * 10_000 + y;
-                        break;
-                    }
-                    None => x += 1,
-                }
-            }
-            _ => panic!("Unexpected output"),
-        }
-    }
-
-    result
+// The coordinates of the closest spot where Santa's 100 x 100 ship fits in the beam.
+fn part2<F>(beam: &mut Beam<F>) -> Option<i128>
+where
+    F: Fn(i128, i128) -> bool,
+{
+    let (x, y) = beam.fit(100, 100)?;
+    Some(x * 10_000 + y)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn traced_scan() {
+        let input = include_str!("./input.txt").trim();
+        let batch = Batch::new(input);
+        let mut beam = Beam::new(|x, y| batch.query(vec![x, y]) == [1]);
+        let scan = beam.scan(MAX, MAX);
+        assert_eq!(226, scan.iter().flatten().filter(|&&pulled| pulled).count());
+        // A fraction of the 2500 points of the scan.
+        assert!(beam.queries() < 400, "{} queries", beam.queries());
+    }
+
+    #[test]
+    fn scan_coverage() {
+        let input = include_str!("./input.txt").trim();
+        let origin = coverage(input, 1);
+        let scan = coverage(input, 10);
+
+        let report = scan.annotate(&Intcode::parse(input));
+        assert_eq!(
+            Some("instructions 117/119, branches 26/48"),
+            report.lines().last()
+        );
+
+        // Points inside and outside the beam take the same paths through the program, so a single
+        // query already reaches everything the scan does.
+        assert!(scan.instructions.keys().eq(origin.instructions.keys()));
+
+        let mut merged = origin.clone();
+        merged.merge(&scan);
+        assert_eq!(
+            scan.instructions.values().sum::<usize>() + origin.instructions.values().sum::<usize>(),
+            merged.instructions.values().sum::<usize>()
+        );
+    }
 }
@@ -1,58 +1,42 @@
 use intcode::Intcode;
 use std::error::Error;
 
+mod springscript;
+use springscript::*;
+
 fn main() -> Result<(), Box<dyn Error>> {
     let input = include_str!("./input.txt").trim();
 
-    // (!A OR !B OR !C) AND D
-    let instructions = "NOT A J
-NOT B T
-OR T J
-NOT C T
-OR T J
-AND D J
-WALK
-"
-    .chars()
-    .map(|c| c as i128)
-    .collect::<Vec<_>>();
-    let mut program = Intcode::new(input, instructions);
-    let output = program.run_until_halt();
-    println!("{:?}", output); // 19358870
-    let output = output
-        .into_iter()
-        .map(|c| ((c as u8) as char))
-        .collect::<String>();
-    print!("out: {}", output);
-
-    // (!A OR !B OR !C) AND D AND (!E OR !H)
-    let instructions = "NOT A J
-NOT B T
-OR T J
-NOT C T
-OR T J
-AND D J
-NOT E T
-AND H T
-OR E T
-AND T J
-RUN
-"
-    .chars()
-    .map(|c| c as i128)
-    .collect::<Vec<_>>();
-
-    let mut program = Intcode::new(input, instructions);
-    let output = program.run_until_halt();
-    println!("{:?}", output); // 19358870
-    let output = output
-        .into_iter()
-        .map(|c| ((c as u8) as char))
-        .collect::<String>();
-    print!("out: {}", output);
+    // Jump when there is a hole in the next three tiles and ground to land on.
+    assert_eq!(19358870, survey(input, "(!A OR !B OR !C) AND D")?);
+
+    // Also make sure the droid can either walk on or jump again after landing.
+    assert_eq!(
+        1143356492,
+        survey(input, "(!A OR !B OR !C) AND D AND (E OR H)")?
+    );
+
     Ok(())
 }
 
+// Runs the droid with the compiled expression and returns the hull damage it reports, or the
+// droid's last moments if it fell into space.
+fn survey(input: &str, source: &str) -> Result<i128, Box<dyn Error>> {
+    let program = compile(source)?;
+    print!("{}", program);
+
+    let mut droid = Intcode::new(input, program.ascii());
+    let output = droid.run_until_halt();
+    match output.last() {
+        Some(&damage) if damage > 127 => Ok(damage),
+        _ => Err(output
+            .into_iter()
+            .map(|c| c as u8 as char)
+            .collect::<String>()
+            .into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,600 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+// The droid only takes this many instructions.
+pub const LIMIT: usize = 15;
+
+// A boolean expression over the sensors A to I, each true when there is ground that many tiles
+// ahead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Sensor(char),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    // Parses expressions like `(!A OR !B OR !C) AND D`. `NOT` or `!` binds tightest, then `AND`
+    // or `&`, then `OR` or `|`.
+    pub fn parse(source: &str) -> Result<Expr, String> {
+        let mut tokens = tokenize(source)?.into_iter().peekable();
+        let expr = parse_or(&mut tokens)?;
+        match tokens.next() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    pub fn sensors(&self) -> BTreeSet<char> {
+        match self {
+            Expr::Sensor(c) => BTreeSet::from([*c]),
+            Expr::Not(e) => e.sensors(),
+            Expr::And(es) | Expr::Or(es) => es.iter().flat_map(Expr::sensors).collect(),
+        }
+    }
+
+    // `ground(c)` tells whether sensor `c` sees ground.
+    pub fn eval<F: Fn(char) -> bool + Copy>(&self, ground: F) -> bool {
+        match self {
+            Expr::Sensor(c) => ground(*c),
+            Expr::Not(e) => !e.eval(ground),
+            Expr::And(es) => es.iter().all(|e| e.eval(ground)),
+            Expr::Or(es) => es.iter().any(|e| e.eval(ground)),
+        }
+    }
+
+    fn not(self) -> Expr {
+        match self {
+            Expr::Not(e) => *e,
+            e => Expr::Not(Box::new(e)),
+        }
+    }
+
+    // Joins expressions with AND or OR, merging nested ones of the same kind.
+    fn join(and: bool, es: Vec<Expr>) -> Expr {
+        let mut joined = vec![];
+        for e in es {
+            match e {
+                Expr::And(inner) if and => joined.extend(inner),
+                Expr::Or(inner) if !and => joined.extend(inner),
+                e => joined.push(e),
+            }
+        }
+        match (joined.len(), and) {
+            (1, _) => joined.pop().unwrap(),
+            (_, true) => Expr::And(joined),
+            (_, false) => Expr::Or(joined),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Sensor(char),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '!' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "NOT" => Token::Not,
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "A" | "B" | "C" | "D" | "E" | "F" | "G" | "H" | "I" => Token::Sensor(c),
+                    word => return Err(format!("unknown word {:?}", word)),
+                }
+            }
+            c => return Err(format!("unexpected {:?}", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+type Tokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
+
+fn parse_or(tokens: &mut Tokens) -> Result<Expr, String> {
+    let mut es = vec![parse_and(tokens)?];
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        es.push(parse_and(tokens)?);
+    }
+    Ok(Expr::join(false, es))
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<Expr, String> {
+    let mut es = vec![parse_not(tokens)?];
+    while tokens.next_if_eq(&Token::And).is_some() {
+        es.push(parse_not(tokens)?);
+    }
+    Ok(Expr::join(true, es))
+}
+
+fn parse_not(tokens: &mut Tokens) -> Result<Expr, String> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(parse_not(tokens)?.not()),
+        Some(Token::Sensor(c)) => Ok(Expr::Sensor(c)),
+        Some(Token::Open) => {
+            let e = parse_or(tokens)?;
+            match tokens.next() {
+                Some(Token::Close) => Ok(e),
+                _ => Err("missing )".to_string()),
+            }
+        }
+        Some(token) => Err(format!("unexpected {:?}", token)),
+        None => Err("unexpected end".to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+// A sensor, or one of the two writable registers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reg {
+    Sensor(char),
+    T,
+    J,
+}
+
+impl Reg {
+    fn index(self) -> usize {
+        match self {
+            Reg::T => 0,
+            Reg::J => 1,
+            Reg::Sensor(_) => unreachable!(),
+        }
+    }
+}
+
+// Only the compiler builds instructions, so `y` is never a sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instruction {
+    op: Op,
+    x: Reg,
+    y: Reg,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reg = |reg: Reg| match reg {
+            Reg::Sensor(c) => c,
+            Reg::T => 'T',
+            Reg::J => 'J',
+        };
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{} {} {}", op, reg(self.x), reg(self.y))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    // Whether the droid needs RUN for sensors E to I, rather than WALK.
+    pub run: bool,
+}
+
+impl Program {
+    // Whether the droid jumps, given what the sensors see.
+    pub fn jumps<F: Fn(char) -> bool>(&self, ground: F) -> bool {
+        let mut regs = [false; 2];
+        for instruction in &self.instructions {
+            let x = match instruction.x {
+                Reg::Sensor(c) => ground(c),
+                reg => regs[reg.index()],
+            };
+            let y = &mut regs[instruction.y.index()];
+            *y = match instruction.op {
+                Op::And => x && *y,
+                Op::Or => x || *y,
+                Op::Not => !x,
+            };
+        }
+        regs[Reg::J.index()]
+    }
+
+    pub fn ascii(&self) -> Vec<i128> {
+        self.to_string().chars().map(|c| c as i128).collect()
+    }
+}
+
+// One instruction per line, ending with WALK or RUN.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        writeln!(f, "{}", if self.run { "RUN" } else { "WALK" })
+    }
+}
+
+// Code generated so far, and what is known of the registers.
+#[derive(Debug, Clone)]
+struct Code {
+    instructions: Vec<Instruction>,
+    // Registers that have not been written yet, and so are still false.
+    clean: [bool; 2],
+}
+
+impl Code {
+    fn emit(mut self, op: Op, x: Reg, y: Reg) -> Self {
+        self.instructions.push(Instruction { op, x, y });
+        self.clean[y.index()] = false;
+        self
+    }
+}
+
+fn shortest(candidates: impl IntoIterator<Item = Option<Code>>) -> Option<Code> {
+    candidates
+        .into_iter()
+        .flatten()
+        .min_by_key(|code| code.instructions.len())
+}
+
+// Writes `e` to `dst`. J may use T as scratch, but T must be computed on its own, since J is
+// holding a value by then. Every way of ordering the operands and of applying De Morgan's laws
+// is tried, unless `flipped` says the caller just applied them.
+fn generate(e: &Expr, dst: Reg, code: Code, flipped: bool) -> Option<Code> {
+    match e {
+        Expr::Sensor(c) => {
+            let x = Reg::Sensor(*c);
+            Some(match code.clean[dst.index()] {
+                true => code.emit(Op::Or, x, dst),
+                false => code.emit(Op::Not, x, dst).emit(Op::Not, dst, dst),
+            })
+        }
+        Expr::Not(inner) => {
+            if let Expr::Sensor(c) = **inner {
+                return Some(code.emit(Op::Not, Reg::Sensor(c), dst));
+            }
+            let negated =
+                generate(inner, dst, code.clone(), false).map(|code| code.emit(Op::Not, dst, dst));
+            let pushed = match (flipped, &**inner) {
+                (false, Expr::And(es)) => {
+                    let es = es.iter().cloned().map(Expr::not).collect();
+                    generate(&Expr::join(false, es), dst, code, true)
+                }
+                (false, Expr::Or(es)) => {
+                    let es = es.iter().cloned().map(Expr::not).collect();
+                    generate(&Expr::join(true, es), dst, code, true)
+                }
+                _ => None,
+            };
+            shortest([negated, pushed])
+        }
+        Expr::And(es) | Expr::Or(es) => {
+            let and = matches!(e, Expr::And(_));
+            let op = if and { Op::And } else { Op::Or };
+            let scratch = (dst == Reg::J).then_some(Reg::T);
+            let mut candidates = (0..es.len())
+                .map(|first| {
+                    let mut code = generate(&es[first], dst, code.clone(), false)?;
+                    for (i, e) in es.iter().enumerate() {
+                        if i != first {
+                            code = combine(op, e, dst, scratch, code)?;
+                        }
+                    }
+                    Some(code)
+                })
+                .collect::<Vec<Option<Code>>>();
+            if dst == Reg::J {
+                candidates.extend((0..es.len()).map(|i| distribute(and, es, i, code.clone())));
+            }
+            if !flipped {
+                let es = es.iter().cloned().map(Expr::not).collect();
+                let flip = Expr::join(!and, es);
+                candidates.push(
+                    generate(&flip, dst, code, true).map(|code| code.emit(Op::Not, dst, dst)),
+                );
+            }
+            shortest(candidates)
+        }
+    }
+}
+
+// Writes `es` joined by AND or OR to J, with the two operands of `es[i]` split between J and T:
+// `R AND (P OR Q)` is `(R AND P) OR (R AND Q)`, with R copied to T. This frees T for one side
+// while J holds the other, as long as P and Q can each be applied without a scratch register.
+fn distribute(and: bool, es: &[Expr], i: usize, code: Code) -> Option<Code> {
+    let (op, inner) = match (and, &es[i]) {
+        (true, Expr::Or(inner)) => (Op::And, inner),
+        (false, Expr::And(inner)) => (Op::Or, inner),
+        _ => return None,
+    };
+    let [p, q] = inner.as_slice() else {
+        return None;
+    };
+    let rest = es
+        .iter()
+        .enumerate()
+        .filter(|&(j, _)| j != i)
+        .map(|(_, e)| e.clone())
+        .collect();
+    let mut code = generate(&Expr::join(and, rest), Reg::J, code, false)?;
+    code = match code.clean[Reg::T.index()] {
+        true => code.emit(Op::Or, Reg::J, Reg::T),
+        false => code
+            .emit(Op::Not, Reg::J, Reg::T)
+            .emit(Op::Not, Reg::T, Reg::T),
+    };
+    for (side, dst) in [(p, Reg::J), (q, Reg::T)] {
+        let terms = match side {
+            Expr::And(terms) if and => terms.clone(),
+            Expr::Or(terms) if !and => terms.clone(),
+            e => vec![e.clone()],
+        };
+        for term in &terms {
+            code = combine(op, term, dst, None, code)?;
+        }
+    }
+    let join = if and { Op::Or } else { Op::And };
+    Some(code.emit(join, Reg::T, Reg::J))
+}
+
+// Combines `dst` with `e` through `op`, computing `e` in `scratch` if it is more than a sensor.
+fn combine(op: Op, e: &Expr, dst: Reg, scratch: Option<Reg>, code: Code) -> Option<Code> {
+    match (e, scratch) {
+        (Expr::Sensor(c), _) => Some(code.emit(op, Reg::Sensor(*c), dst)),
+        (Expr::Not(inner), _) if matches!(**inner, Expr::Sensor(_)) => {
+            let Expr::Sensor(c) = **inner else {
+                unreachable!()
+            };
+            let x = Reg::Sensor(c);
+            // dst AND !x is !(!dst OR x), and dst OR !x is !(!dst AND x).
+            let flip = if op == Op::And { Op::Or } else { Op::And };
+            let in_place = Some(
+                code.clone()
+                    .emit(Op::Not, dst, dst)
+                    .emit(flip, x, dst)
+                    .emit(Op::Not, dst, dst),
+            );
+            let through_scratch = scratch.map(|s| code.emit(Op::Not, x, s).emit(op, s, dst));
+            shortest([in_place, through_scratch])
+        }
+        (e, Some(s)) => generate(e, s, code, false).map(|code| code.emit(op, s, dst)),
+        (_, None) => None,
+    }
+}
+
+// Compiles an expression telling when the droid should jump, into the shortest program the
+// search in `generate` finds. That follows the shape of the expression and never simplifies it,
+// so a shorter program may exist.
+pub fn compile(source: &str) -> Result<Program, String> {
+    let expr = Expr::parse(source)?;
+    let code = Code {
+        instructions: vec![],
+        clean: [true; 2],
+    };
+    let code = generate(&expr, Reg::J, code, false)
+        .ok_or("the expression is too nested for the T and J registers")?;
+    if code.instructions.len() > LIMIT {
+        return Err(format!(
+            "{} instructions, more than the {} the droid takes",
+            code.instructions.len(),
+            LIMIT
+        ));
+    }
+    let program = Program {
+        instructions: code.instructions,
+        run: expr.sensors().iter().any(|&c| c > 'D'),
+    };
+
+    // The droid only finds out about a wrong program by falling, so check every reading first.
+    for readings in 0..1u32 << 9 {
+        let ground = |c: char| readings & 1 << (c as u32 - 'A' as u32) != 0;
+        if expr.eval(ground) != program.jumps(ground) {
+            return Err(format!("miscompiled {:?}:\n{}", source, program));
+        }
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // Checks the program against the expression for everything the sensors can see.
+    fn equivalent(source: &str, program: &Program) -> bool {
+        let expr = Expr::parse(source).unwrap();
+        (0..1 << 9).all(|ground: u32| {
+            let ground = |c: char| ground & 1 << (c as u32 - 'A' as u32) != 0;
+            expr.eval(ground) == program.jumps(ground)
+        })
+    }
+
+    #[test]
+    fn parse() {
+        use Expr::*;
+        assert_eq!(
+            And(vec![
+                Or(vec![Not(Box::new(Sensor('A'))), Sensor('B')]),
+                Sensor('D'),
+            ]),
+            Expr::parse("(NOT A | B) AND D").unwrap()
+        );
+        assert_eq!(Sensor('A'), Expr::parse("!!A").unwrap());
+        assert_eq!(
+            Err("unknown word \"X\"".to_string()),
+            Expr::parse("A AND X")
+        );
+        assert_eq!(Err("missing )".to_string()), Expr::parse("(A OR B"));
+    }
+
+    #[test]
+    fn compile_walk() {
+        let source = "(!A OR !B OR !C) AND D";
+        let program = compile(source).unwrap();
+        assert_eq!(
+            "OR A J\nAND B J\nAND C J\nNOT J J\nAND D J\nWALK\n",
+            program.to_string()
+        );
+        assert!(equivalent(source, &program));
+    }
+
+    #[test]
+    fn compile_run() {
+        let source = "(!A OR !B OR !C) AND D AND (E OR H)";
+        let program = compile(source).unwrap();
+        assert!(program.run);
+        assert_eq!(8, program.instructions.len());
+        assert!(equivalent(source, &program));
+    }
+
+    #[test]
+    fn equivalence() {
+        for source in [
+            "A",
+            "!A",
+            "A AND !B",
+            "!A OR !B",
+            "!(A AND B) AND !(C OR D)",
+            "(A AND B) OR (C AND D)",
+            "(A OR !B) AND (C OR !D) AND !E",
+            "!(A OR B OR C) OR (D AND !E AND (F OR !G))",
+            "(A & B | C) & !(D | !E & F) & (G | H | !I)",
+        ] {
+            let program = compile(source).unwrap();
+            assert!(equivalent(source, &program), "{}:\n{}", source, program);
+        }
+    }
+
+    #[test]
+    fn distribute() {
+        // Both sides of the outer AND need a register of their own, so the left one is copied
+        // to T and the right one's halves are applied to each copy.
+        let source = "((A AND B) OR (C AND D)) AND ((E AND F) OR (G AND H))";
+        let program = compile(source).unwrap();
+        assert_eq!(12, program.instructions.len(), "{}", program);
+        assert!(equivalent(source, &program));
+
+        let source = "((A OR B) AND (C OR D)) OR ((!E OR F) AND (G OR !H))";
+        assert!(equivalent(source, &compile(source).unwrap()));
+    }
+
+    // Length of the shortest program for an expression over at most four sensors, by a search
+    // over every pair of values T and J can hold, as truth tables over the sensor readings.
+    fn shortest_possible(expr: &Expr) -> usize {
+        let sensors = expr.sensors().into_iter().collect::<Vec<char>>();
+        assert!(sensors.len() <= 4);
+        let readings = 1 << sensors.len();
+        let reads = |c: char, reading: usize| {
+            let i = sensors.iter().position(|&s| s == c).unwrap();
+            reading >> i & 1 == 1
+        };
+        let table = |f: &dyn Fn(usize) -> bool| {
+            (0..readings)
+                .filter(|&reading| f(reading))
+                .fold(0u16, |table, reading| table | 1 << reading)
+        };
+        let all = table(&|_| true);
+        let sensors = sensors
+            .iter()
+            .map(|&c| table(&|reading| reads(c, reading)))
+            .collect::<Vec<u16>>();
+        let target = table(&|reading| expr.eval(|c| reads(c, reading)));
+
+        let mut seen = HashSet::from([(0, 0)]);
+        let mut frontier = vec![(0u16, 0u16)];
+        for length in 0.. {
+            if frontier.iter().any(|&(_, j)| j == target) {
+                return length;
+            }
+            let mut next = vec![];
+            for &(t, j) in &frontier {
+                for &x in sensors.iter().chain([t, j].iter()) {
+                    for (y, to_t) in [(t, true), (j, false)] {
+                        for value in [x & y, x | y, !x & all] {
+                            let regs = if to_t { (value, j) } else { (t, value) };
+                            if seen.insert(regs) {
+                                next.push(regs);
+                            }
+                        }
+                    }
+                }
+            }
+            frontier = next;
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn shortest() {
+        for source in [
+            "A",
+            "!A",
+            "A AND !B",
+            "!A OR !B",
+            "!A AND (B OR C)",
+            "(A AND B) OR C",
+            "(!A OR !B OR !C) AND D",
+            "!(A AND B) AND !(C OR D)",
+            "(A AND B) OR (C AND D)",
+            "(A AND !B) OR (!A AND B)",
+            "!(A OR B) OR (C AND !D)",
+        ] {
+            let expr = Expr::parse(source).unwrap();
+            let program = compile(source).unwrap();
+            assert_eq!(
+                shortest_possible(&expr),
+                program.instructions.len(),
+                "{}:\n{}",
+                source,
+                program
+            );
+        }
+
+        // Expressions that simplify, which the compiler takes as written.
+        for (source, found, possible) in [
+            ("(A OR !B) AND (C OR !D) AND !B", 7, 4),
+            ("(A AND B) OR (C AND D) OR (A AND D)", 9, 6),
+        ] {
+            let expr = Expr::parse(source).unwrap();
+            assert_eq!(possible, shortest_possible(&expr), "{}", source);
+            assert_eq!(
+                found,
+                compile(source).unwrap().instructions.len(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn limits() {
+        // The compiler finds nothing within the 15 instructions for this one.
+        assert!(compile(
+            "(!A AND !B AND !C AND !D) OR (A AND B AND C AND !D) OR (A AND !B AND !C AND D) \
+             OR (!A AND B AND !C AND D) OR (!A AND !B AND C AND D)"
+        )
+        .unwrap_err()
+        .contains("more than the 15"));
+    }
+}
@@ -1,9 +1,7 @@
+use intcode::runtime::{yield_now, AsyncIntcode, Channel, Executor};
 use intcode::Intcode;
-use std::collections::HashMap;
 use std::error::Error;
 
-type Packet = (i128, i128);
-
 fn main() -> Result<(), Box<dyn Error>> {
     let input = include_str!("./input.txt");
     let input = input.trim();
@@ -13,100 +11,111 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn part1(input: &str) -> i128 {
-    let mut computers = (0..50)
-        .map(|address| Intcode::new(input, vec![address, -1]))
-        .collect::<Vec<Intcode>>();
-
-    let mut i = 0;
-
-    loop {
-        let computer = computers.get_mut(i).unwrap();
-        // If not input, set it to -1.
-        if computer.input.is_empty() {
-            computer.input.push(-1);
-        }
-        computer.exec();
-        let output = computer.output.clone();
-        computer.output.clear();
-        for i in 0..output.len() / 3 {
-            let address = output[i * 3];
-            let x = output[i * 3 + 1];
-            let y = output[i * 3 + 2];
+struct Network {
+    executor: Executor,
+    // Packets waiting to be read by each computer.
+    inputs: Vec<Channel>,
+    // Values written by each computer that have not been routed yet.
+    outputs: Vec<Channel>,
+    // Packets sent to address 255.
+    nat: Channel,
+}
 
-            if address == 255 {
-                return y;
+// Boots the 50 computers, with a router task per computer that forwards its (address, x, y)
+// packets.
+fn network(input: &str) -> Network {
+    let mut executor = Executor::new();
+    let inputs = (0..50)
+        .map(|address| {
+            let channel = Channel::new();
+            channel.send(address);
+            channel
+        })
+        .collect::<Vec<Channel>>();
+    let outputs = (0..50).map(|_| Channel::new()).collect::<Vec<Channel>>();
+    let nat = Channel::new();
+
+    for (input_channel, output) in inputs.iter().zip(outputs.iter()) {
+        let computer = AsyncIntcode::new(
+            Intcode::new(input, vec![]),
+            input_channel.clone(),
+            output.clone(),
+        )
+        .nonblocking(-1);
+        executor.spawn(async move {
+            computer.await;
+        });
+
+        let (inputs, output, nat) = (inputs.clone(), output.clone(), nat.clone());
+        executor.spawn(async move {
+            loop {
+                let address = output.recv().await;
+                let x = output.recv().await;
+                let y = output.recv().await;
+                let destination = match address {
+                    255 => &nat,
+                    _ => &inputs[address as usize],
+                };
+                destination.send(x);
+                destination.send(y);
             }
+        });
+    }
 
-            let computer = computers.get_mut(address as usize).unwrap();
-            computer.input.push(x);
-            computer.input.push(y);
-        }
-
-        i += 1;
-        i %= computers.len();
+    Network {
+        executor,
+        inputs,
+        outputs,
+        nat,
     }
 }
 
-fn part2(input: &str) -> i128 {
-    let mut computers = (0..50)
-        .map(|address| Intcode::new(input, vec![address, -1]))
-        .collect::<Vec<Intcode>>();
-
-    let mut i = 0;
-    let mut nat: Packet = (0, 0);
-    let mut cache: HashMap<Packet, usize> = HashMap::new();
-    cache.insert(nat, 0);
-    let mut cycle: HashMap<usize, usize> = HashMap::new();
-
-    loop {
-        if let Some(num_delivered) = cache.get(&nat) {
-            if *num_delivered >= 2 {
-                return nat.1;
-            }
-        }
-
-        let n = computers.len();
-        let computer = computers.get_mut(i % n).unwrap();
-        if computer.input.is_empty() {
-            computer.input.push(-1);
-
-            // Store the idle state.
-            let count = cycle.entry(i / n).or_insert(0);
-            *count += 1;
-        }
-
-        computer.exec();
-        let output = computer.output.clone();
-        computer.output.clear();
-        for i in 0..output.len() / 3 {
-            let address = output[i * 3];
-            let x = output[i * 3 + 1];
-            let y = output[i * 3 + 2];
-
-            if address == 255 {
-                nat = (x, y);
-            } else {
-                let computer = computers.get_mut(address as usize).unwrap();
-                computer.input.push(x);
-                computer.input.push(y);
-            }
-        }
-
-        match cycle.get(&(i / n)) {
-            Some(&count) if count == n => {
-                let packet = cache.entry(nat).or_insert(0);
-                *packet += 1;
+fn part1(input: &str) -> i128 {
+    let Network {
+        mut executor, nat, ..
+    } = network(input);
+
+    executor
+        .block_on(async {
+            let _x = nat.recv().await;
+            nat.recv().await
+        })
+        .unwrap()
+}
 
-                let computer = computers.get_mut(0).unwrap();
-                computer.input.push(nat.0);
-                computer.input.push(nat.1);
+fn part2(input: &str) -> i128 {
+    let Network {
+        mut executor,
+        inputs,
+        outputs,
+        nat,
+    } = network(input);
+
+    executor
+        .block_on(async {
+            let mut packet = None;
+            let mut delivered = None;
+            loop {
+                yield_now().await;
+                while !nat.is_empty() {
+                    packet = Some((nat.try_recv().unwrap(), nat.try_recv().unwrap()));
+                }
+
+                // The network is idle when every computer has read -1 twice since its last packet
+                // and nothing is left to route.
+                let idle = inputs.iter().all(|input| input.misses() >= 2)
+                    && outputs.iter().all(Channel::is_empty);
+                if let (true, Some((x, y))) = (idle, packet) {
+                    if delivered == Some(y) {
+                        return y;
+                    }
+                    inputs[0].send(x);
+                    inputs[0].send(y);
+                    delivered = Some(y);
+                }
             }
-            _ => {}
-        }
-
-        i += 1;
-    }
+        })
+        .unwrap()
 }
 
 #[cfg(test)]
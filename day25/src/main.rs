@@ -1,39 +1,53 @@
+use intcode::session::{Recorder, Transcript};
 use intcode::{Intcode, Signal};
+use std::env;
+use std::error::Error;
 use std::io;
 use std::io::prelude::*;
 
-fn main() {
+const TRANSCRIPT: &str = "./transcript.txt";
+
+fn main() -> Result<(), Box<dyn Error>> {
     let input = include_str!("./input.txt").trim();
-    let mut program = Intcode::new(&input, vec![]);
+    let mut program = Intcode::new(input, vec![]);
+
+    // Replay a previous session with `cargo run -- --replay transcript.txt`.
+    let args = env::args().collect::<Vec<String>>();
+    if let [_, flag, path] = &args[..] {
+        if flag == "--replay" {
+            Transcript::load(path)?.replay(&mut program)?;
+            println!("replayed {} without divergence", path);
+            return Ok(());
+        }
+    }
 
     // Take the klein bottle, mutex, hypercube and mug at the security checkpoint.
+    let mut recorder = Recorder::new();
     loop {
-        match program.exec() {
+        let signal = recorder.exec(&mut program);
+        let output = program.output.clone();
+        program.output.clear();
+        let output = output
+            .into_iter()
+            .map(|n| (n as u8) as char)
+            .collect::<String>();
+        print_single_line(&output);
+
+        match signal {
             Signal::Waiting => {
-                let output = program.output.clone();
-                program.output.clear();
-                let input = output
-                    .into_iter()
-                    .map(|n| (n as u8) as char)
-                    .collect::<String>();
-                print_single_line(&input);
                 let input = read_line();
-                let input = input.chars().map(|c| c as i128).collect::<Vec<i128>>();
-                program.input.append(&mut input.clone());
-            }
-            Signal::Halt => {
-                let output = program.output.clone();
-                program.output.clear();
-                let input = output
-                    .into_iter()
-                    .map(|n| (n as u8) as char)
-                    .collect::<String>();
-                print_single_line(&input);
-                break;
+                if input.is_empty() {
+                    break;
+                }
+                program.input.extend(input.chars().map(|c| c as i128));
             }
+            Signal::Halt => break,
         }
     }
-    program.run_until_halt();
+
+    recorder.transcript().save(TRANSCRIPT)?;
+    println!("session saved to {}", TRANSCRIPT);
+    Ok(())
 }
 
 fn print_single_line(input: &str) {
@@ -51,9 +65,38 @@ fn read_line() -> String {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn replay_reaches_the_same_state() {
+        let input = include_str!("./input.txt").trim();
+
+        // Walk north and back, then check the inventory, as the interactive loop would.
+        let mut program = Intcode::new(input, vec![]);
+        let mut recorder = Recorder::new();
+        for command in ["north\n", "south\n", "inv\n"] {
+            assert_eq!(Signal::Waiting, recorder.exec(&mut program));
+            program.input.extend(command.chars().map(|c| c as i128));
+        }
+        assert_eq!(Signal::Waiting, recorder.exec(&mut program));
+
+        // Go through a file, like `--replay` does.
+        let path = env::temp_dir().join(format!("day25-replay-{}.txt", std::process::id()));
+        recorder.transcript().save(&path).unwrap();
+        let transcript = Transcript::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut replayed = Intcode::new(input, vec![]);
+        transcript.unwrap().replay(&mut replayed).unwrap();
+        assert_eq!(program.ip(), replayed.ip());
+        assert_eq!(program.relative_base(), replayed.relative_base());
+        assert_eq!(program.output, replayed.output);
+        assert_eq!(program.memory(), replayed.memory());
+    }
 }
@@ -0,0 +1,67 @@
+// Compares forking a machine against copying its whole image, using the breadth-first search from
+// day15 that forks one repair droid per branch. Run with `cargo bench`.
+use intcode::{Intcode, Signal};
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 5;
+
+fn search(droid: Intcode, fork: fn(&Intcode) -> Intcode) -> (i32, usize) {
+    let mut depth = 0;
+    let mut forks = 0;
+    let mut droids = vec![(0, droid)];
+
+    while !droids.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for (prev, droid) in droids {
+            for i in 1..=4 {
+                // Don't move back to where the droid came from.
+                if [0, 2, 1, 4, 3][i as usize] == prev {
+                    continue;
+                }
+                let mut droid = fork(&droid);
+                forks += 1;
+                droid.set_input(i as i128);
+                if droid.exec() == Signal::Waiting {
+                    match droid.output.pop() {
+                        Some(1) => next.push((i, droid)),
+                        Some(2) => return (depth, forks),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        droids = next;
+    }
+    (depth, forks)
+}
+
+fn bench(name: &str, fork: fn(&Intcode) -> Intcode) {
+    let input = include_str!("../../day15/src/input.txt").trim();
+    let mut total = Duration::ZERO;
+    let mut result = (0, 0);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = search(Intcode::from(input.to_string()), fork);
+        total += start.elapsed();
+    }
+    println!(
+        "{:>6}: {:?} per search, {} steps, {} forks",
+        name,
+        total / RUNS,
+        result.0,
+        result.1
+    );
+}
+
+fn main() {
+    bench("fork", Intcode::fork);
+    bench("copy", |droid| {
+        // Writing every cell copies every page.
+        let mut droid = droid.clone();
+        for addr in 0..droid.memory().len() {
+            droid.poke(addr, droid.peek(addr));
+        }
+        droid
+    });
+}
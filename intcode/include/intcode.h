@@ -0,0 +1,42 @@
+/* Generated from intcode/src/ffi.rs by `INTCODE_BLESS=1 cargo test`. */
+#ifndef INTCODE_H
+#define INTCODE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct Intcode Intcode;
+
+#define INTCODE_ERROR -1
+#define INTCODE_RUNNING 0
+#define INTCODE_WAITING 1
+#define INTCODE_HALTED 2
+
+/* Creates a machine from `len` values. Returns NULL if `codes` is NULL. */
+Intcode *intcode_new(const int64_t *codes, size_t len);
+
+/* Queues a value for the next input instruction. */
+void intcode_push_input(Intcode *program, int64_t value);
+
+/* Runs until the machine waits for input or halts, and returns INTCODE_WAITING or INTCODE_HALTED. Returns INTCODE_ERROR if the program is invalid. */
+int32_t intcode_run(Intcode *program);
+
+/* Takes the oldest output into `value`. Returns 1 if there was one, 0 if there are no outputs, or INTCODE_ERROR if it does not fit in 64 bits, in which case it stays queued. */
+int32_t intcode_pop_output(Intcode *program, int64_t *value);
+
+/* Returns INTCODE_RUNNING, INTCODE_WAITING or INTCODE_HALTED. */
+int32_t intcode_state(const Intcode *program);
+
+/* Returns the instruction pointer. */
+size_t intcode_ip(const Intcode *program);
+
+/* Returns the value at `addr`, truncated to 64 bits. */
+int64_t intcode_peek(const Intcode *program, size_t addr);
+
+/* Returns the number of instructions executed so far. */
+uint64_t intcode_steps(const Intcode *program);
+
+/* Frees the machine. Does nothing if `program` is NULL. */
+void intcode_free(Intcode *program);
+
+#endif
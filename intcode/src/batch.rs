@@ -0,0 +1,113 @@
+use crate::Intcode;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Runs many independent queries against the same program, e.g. one (x, y) query per point of
+// day19's tractor beam. The program is parsed once, and every query forks the shared image.
+pub struct Batch {
+    image: Intcode,
+    threads: usize,
+    memo: Option<Mutex<HashMap<Vec<i128>, Vec<i128>>>>,
+}
+
+impl Batch {
+    pub fn new(code: &str) -> Self {
+        Batch {
+            image: Intcode::from(code.to_string()),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            memo: None,
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Caches the outputs of every input. Only valid for programs whose outputs depend on nothing
+    // but their inputs.
+    pub fn memoize(mut self) -> Self {
+        self.memo = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    // Runs the program with the given inputs until it halts or runs out of input, and returns
+    // its outputs.
+    pub fn query(&self, input: Vec<i128>) -> Vec<i128> {
+        if let Some(memo) = &self.memo {
+            if let Some(output) = memo.lock().unwrap().get(&input) {
+                return output.clone();
+            }
+        }
+
+        let mut program = self.image.fork();
+        program.with_input(input.clone());
+        program.exec();
+
+        if let Some(memo) = &self.memo {
+            memo.lock().unwrap().insert(input, program.output.clone());
+        }
+        program.output
+    }
+
+    // Runs every query across the thread pool. Outputs are returned in the order of the inputs.
+    pub fn run(&self, inputs: &[Vec<i128>]) -> Vec<Vec<i128>> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![vec![]; inputs.len()]);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(inputs.len()) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= inputs.len() {
+                        break;
+                    }
+                    let output = self.query(inputs[i].clone());
+                    results.lock().unwrap()[i] = output;
+                });
+            }
+        });
+
+        results.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs 1 if x < y.
+    const LESS: &str = "3,11,3,12,7,11,12,13,4,13,99,0,0,0";
+
+    #[test]
+    fn run() {
+        let inputs = (0..5)
+            .flat_map(|x| (0..5).map(move |y| vec![x, y]))
+            .collect::<Vec<Vec<i128>>>();
+        let expected = inputs
+            .iter()
+            .map(|input| vec![(input[0] < input[1]) as i128])
+            .collect::<Vec<Vec<i128>>>();
+
+        assert_eq!(expected, Batch::new(LESS).threads(4).run(&inputs));
+        assert_eq!(expected, Batch::new(LESS).threads(1).run(&inputs));
+    }
+
+    #[test]
+    fn memoize() {
+        let batch = Batch::new(LESS).memoize();
+        assert_eq!(vec![1], batch.query(vec![2, 3]));
+        assert_eq!(vec![0], batch.query(vec![3, 3]));
+        assert_eq!(2, batch.memo.as_ref().unwrap().lock().unwrap().len());
+        assert_eq!(vec![1], batch.query(vec![2, 3]));
+        assert_eq!(2, batch.memo.as_ref().unwrap().lock().unwrap().len());
+    }
+
+    #[test]
+    fn starved() {
+        // Stops at the second input instead of waiting forever.
+        assert_eq!(Vec::<i128>::new(), Batch::new(LESS).query(vec![2]));
+    }
+}
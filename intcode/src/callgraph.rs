@@ -0,0 +1,212 @@
+use crate::{Instruction, Intcode, Operation, ParameterMode, Signal, State};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// An active call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    // Entry point of the called function.
+    pub entry: usize,
+    // Address of the jump that made the call.
+    pub call_site: usize,
+    pub return_to: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Call { from: usize, to: usize },
+    Return { from: usize, to: usize },
+}
+
+// Recovers calls and returns from a run. The puzzle programs call a function by storing the
+// return address at [rb+0] and jumping, and return with an indirect jump through the stored
+// address. So a taken jump made while [rb+0] holds the address after it is a call, and an
+// indirect jump to the return address of an active call is a return from it.
+//
+// Drive the machine through `step` or `exec` instead of the machine's own.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    stack: Vec<Frame>,
+    // Entry points of every function called so far.
+    pub functions: BTreeSet<usize>,
+    // Number of calls from one function to another, keyed by their entry points. Code that is
+    // not inside any call belongs to the function at 0.
+    pub calls: BTreeMap<(usize, usize), usize>,
+}
+
+impl CallGraph {
+    pub fn new() -> Self {
+        CallGraph::default()
+    }
+
+    // Active calls, outermost first.
+    pub fn stack(&self) -> &[Frame] {
+        &self.stack
+    }
+
+    // Entry point of the function the machine is in.
+    pub fn function(&self) -> usize {
+        self.stack.last().map_or(0, |frame| frame.entry)
+    }
+
+    // Records the next instruction, unless the machine is waiting for input.
+    pub fn record(&mut self, program: &Intcode) -> Option<Event> {
+        if program.state() == State::Waiting {
+            return None;
+        }
+        let instruction = Instruction::at(program)?;
+        let condition = match instruction.op {
+            Operation::JumpIfTrue => instruction.value(0, program) != 0,
+            Operation::JumpIfFalse => instruction.value(0, program) == 0,
+            _ => return None,
+        };
+        if !condition {
+            return None;
+        }
+
+        let ip = program.ip();
+        let target = instruction.value(1, program);
+        if target < 0 {
+            return None;
+        }
+        let target = target as usize;
+        let next = ip + instruction.size();
+
+        let indirect = instruction.params[1].0 != ParameterMode::Immediate;
+        if indirect {
+            if let Some(depth) = self
+                .stack
+                .iter()
+                .rposition(|frame| frame.return_to == target)
+            {
+                // Frames above the returning one made tail calls.
+                self.stack.truncate(depth);
+                return Some(Event::Return {
+                    from: ip,
+                    to: target,
+                });
+            }
+        }
+
+        let base = program.relative_base();
+        if base >= 0 && program.peek(base as usize) == next as i128 {
+            *self.calls.entry((self.function(), target)).or_insert(0) += 1;
+            self.functions.insert(target);
+            self.stack.push(Frame {
+                entry: target,
+                call_site: ip,
+                return_to: next,
+            });
+            return Some(Event::Call {
+                from: ip,
+                to: target,
+            });
+        }
+        None
+    }
+
+    pub fn step(&mut self, program: &mut Intcode) -> Option<Signal> {
+        self.record(program);
+        program.step()
+    }
+
+    pub fn exec(&mut self, program: &mut Intcode) -> Signal {
+        loop {
+            if let Some(signal) = self.step(program) {
+                return signal;
+            }
+        }
+    }
+
+    // The active calls, innermost first, starting at `ip`:
+    //
+    //     #0  ip 350 in fn 303
+    //     #1  ip 381 in fn 303
+    //     #2  ip 336 in fn 0
+    pub fn backtrace(&self, ip: usize) -> Vec<String> {
+        let mut lines = vec![format!("#0  ip {} in fn {}", ip, self.function())];
+        for (i, frame) in self.stack.iter().enumerate().rev() {
+            let caller = if i == 0 { 0 } else { self.stack[i - 1].entry };
+            lines.push(format!(
+                "#{}  ip {} in fn {}",
+                self.stack.len() - i,
+                frame.call_site,
+                caller
+            ));
+        }
+        lines
+    }
+}
+
+// Prints one `caller -> callee x count` edge per line.
+impl fmt::Display for CallGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ((caller, callee), n) in self.calls.iter() {
+            writeln!(f, "fn {} -> fn {} x {}", caller, callee, n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Computes the input's factorial recursively, with the calling convention of the puzzles.
+    const FACTORIAL: &str = "\
+109,100,\
+3,1000,\
+21001,1000,0,1,\
+21101,0,15,0,\
+1105,1,18,\
+204,2,\
+99,\
+109,3,\
+1205,-2,32,\
+21101,0,1,-1,\
+109,-3,\
+2105,1,0,\
+22101,-1,-2,1,\
+21101,0,43,0,\
+1105,1,18,\
+22202,-2,2,-1,\
+109,-3,\
+2105,1,0";
+
+    #[test]
+    fn recursion() {
+        let mut program = Intcode::new(FACTORIAL, vec![4]);
+        let mut graph = CallGraph::new();
+        assert_eq!(Signal::Halt, graph.exec(&mut program));
+        assert_eq!(vec![24], program.output);
+
+        assert_eq!(
+            vec![18],
+            graph.functions.iter().copied().collect::<Vec<usize>>()
+        );
+        assert_eq!(Some(&1), graph.calls.get(&(0, 18)));
+        assert_eq!(Some(&4), graph.calls.get(&(18, 18)));
+        assert!(graph.stack().is_empty(), "every call returned");
+        assert_eq!("fn 0 -> fn 18 x 1\nfn 18 -> fn 18 x 4\n", graph.to_string());
+    }
+
+    #[test]
+    fn backtrace() {
+        let mut program = Intcode::new(FACTORIAL, vec![2]);
+        let mut graph = CallGraph::new();
+
+        // Run until the innermost call, factorial(0), is about to return.
+        while graph.stack().len() < 3 || program.peek(program.ip()) != 2105 {
+            graph.step(&mut program);
+        }
+        assert_eq!(
+            vec![
+                "#0  ip 29 in fn 18",
+                "#1  ip 40 in fn 18",
+                "#2  ip 40 in fn 18",
+                "#3  ip 12 in fn 0",
+            ],
+            graph.backtrace(program.ip())
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{Intcode, Signal};
+
+// What a conforming machine must produce for an example.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expect {
+    Output(&'static [i128]),
+    // The whole memory after the program halts.
+    Memory(&'static [i128]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Example {
+    pub name: &'static str,
+    pub code: &'static str,
+    pub input: &'static [i128],
+    pub expect: Expect,
+}
+
+const fn output(
+    name: &'static str,
+    code: &'static str,
+    input: &'static [i128],
+    output: &'static [i128],
+) -> Example {
+    Example {
+        name,
+        code,
+        input,
+        expect: Expect::Output(output),
+    }
+}
+
+const fn memory(name: &'static str, code: &'static str, memory: &'static [i128]) -> Example {
+    Example {
+        name,
+        code,
+        input: &[],
+        expect: Expect::Memory(memory),
+    }
+}
+
+const DAY05_COMPARE: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,\
+1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+const DAY09_QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
+// Every example program published with the puzzles that runs on a single machine, and checks for
+// behaviour the examples do not cover. The day07 amplifier examples need several machines and
+// are run by day07 itself.
+pub const EXAMPLES: &[Example] = &[
+    memory("day02 add", "1,0,0,0,99", &[2, 0, 0, 0, 99]),
+    memory("day02 mul", "2,3,0,3,99", &[2, 3, 0, 6, 99]),
+    memory(
+        "day02 mul past halt",
+        "2,4,4,5,99,0",
+        &[2, 4, 4, 5, 99, 9801],
+    ),
+    memory(
+        "day02 self-modifying",
+        "1,1,1,4,99,5,6,0,99",
+        &[30, 1, 1, 4, 2, 5, 6, 0, 99],
+    ),
+    memory(
+        "day02 walkthrough",
+        "1,9,10,3,2,3,11,0,99,30,40,50",
+        &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50],
+    ),
+    output("day05 echo", "3,0,4,0,99", &[42], &[42]),
+    memory(
+        "day05 parameter modes",
+        "1002,4,3,4,33",
+        &[1002, 4, 3, 4, 99],
+    ),
+    memory(
+        "day05 negative values",
+        "1101,100,-1,4,0",
+        &[1101, 100, -1, 4, 99],
+    ),
+    output(
+        "day05 eq 8 position",
+        "3,9,8,9,10,9,4,9,99,-1,8",
+        &[8],
+        &[1],
+    ),
+    output(
+        "day05 ne 8 position",
+        "3,9,8,9,10,9,4,9,99,-1,8",
+        &[7],
+        &[0],
+    ),
+    output(
+        "day05 lt 8 position",
+        "3,9,7,9,10,9,4,9,99,-1,8",
+        &[7],
+        &[1],
+    ),
+    output(
+        "day05 ge 8 position",
+        "3,9,7,9,10,9,4,9,99,-1,8",
+        &[8],
+        &[0],
+    ),
+    output("day05 eq 8 immediate", "3,3,1108,-1,8,3,4,3,99", &[8], &[1]),
+    output("day05 ne 8 immediate", "3,3,1108,-1,8,3,4,3,99", &[9], &[0]),
+    output(
+        "day05 lt 8 immediate",
+        "3,3,1107,-1,8,3,4,3,99",
+        &[-3],
+        &[1],
+    ),
+    output("day05 ge 8 immediate", "3,3,1107,-1,8,3,4,3,99", &[9], &[0]),
+    output(
+        "day05 jump zero position",
+        "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        &[0],
+        &[0],
+    ),
+    output(
+        "day05 jump non-zero position",
+        "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        &[5],
+        &[1],
+    ),
+    output(
+        "day05 jump zero immediate",
+        "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        &[0],
+        &[0],
+    ),
+    output(
+        "day05 jump non-zero immediate",
+        "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        &[-5],
+        &[1],
+    ),
+    output("day05 compare below 8", DAY05_COMPARE, &[7], &[999]),
+    output("day05 compare 8", DAY05_COMPARE, &[8], &[1000]),
+    output("day05 compare above 8", DAY05_COMPARE, &[9], &[1001]),
+    output(
+        "day09 quine",
+        DAY09_QUINE,
+        &[],
+        &[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ],
+    ),
+    output(
+        "day09 16-digit number",
+        "1102,34915192,34915192,7,4,7,99,0",
+        &[],
+        &[1219070632396864],
+    ),
+    output(
+        "day09 large number",
+        "104,1125899906842624,99",
+        &[],
+        &[1125899906842624],
+    ),
+    output(
+        "larger than 64 bits",
+        "1102,9223372036854775807,4,7,4,7,99,0",
+        &[],
+        &[36893488147419103228],
+    ),
+    output("relative input", "109,10,203,0,204,0,99", &[7], &[7]),
+    output("relative add", "109,100,21101,3,4,-1,204,-1,99", &[], &[7]),
+    output(
+        "relative base adjusted twice",
+        "109,5,109,-3,22201,0,1,0,204,0,99",
+        &[],
+        &[106],
+    ),
+    output("read past the image", "4,100,99", &[], &[0]),
+    memory(
+        "write past the image",
+        "1101,1,2,8,99",
+        &[1101, 1, 2, 8, 99, 0, 0, 0, 3],
+    ),
+    output("grow then read", "1101,1,2,1000,4,1000,99", &[], &[3]),
+];
+
+// Runs an example until it halts, and compares the outputs or memory with the expected ones.
+pub fn check(example: &Example) -> Result<(), String> {
+    let mut program = Intcode::new(example.code, example.input.to_vec());
+    if program.exec() != Signal::Halt {
+        return Err(format!("{}: waiting for input", example.name));
+    }
+
+    match example.expect {
+        Expect::Output(output) if program.output != output => Err(format!(
+            "{}: expected output {:?}, got {:?}",
+            example.name, output, program.output
+        )),
+        Expect::Memory(memory) if program.memory().to_vec() != memory => Err(format!(
+            "{}: expected memory {:?}, got {:?}",
+            example.name,
+            memory,
+            program.memory()
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corpus() {
+        let failures = EXAMPLES
+            .iter()
+            .filter_map(|example| check(example).err())
+            .collect::<Vec<String>>();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn check_reports_mismatch() {
+        let example = output("wrong", "104,1,99", &[], &[2]);
+        assert_eq!(
+            Err("wrong: expected output [2], got [1]".to_string()),
+            check(&example)
+        );
+        let example = output("starved", "3,0,99", &[], &[]);
+        assert_eq!(
+            Err("starved: waiting for input".to_string()),
+            check(&example)
+        );
+    }
+}
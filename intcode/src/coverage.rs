@@ -0,0 +1,161 @@
+use crate::disasm::disassemble;
+use crate::{Instruction, Intcode, Operation, Signal, State};
+use std::collections::BTreeMap;
+
+// How often a conditional jump went each way.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Branch {
+    pub taken: usize,
+    pub not_taken: usize,
+}
+
+// Instruction and branch coverage of one or more runs. Drive the machine through `step` or
+// `exec` instead of the machine's own, and `merge` the coverage of separate runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    // Times each instruction was executed, by address.
+    pub instructions: BTreeMap<usize, usize>,
+    // Outcomes of each JumpIfTrue and JumpIfFalse, by address.
+    pub branches: BTreeMap<usize, Branch>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    // Records the next instruction, unless the machine is waiting for input.
+    pub fn record(&mut self, program: &Intcode) {
+        if program.state() == State::Waiting {
+            return;
+        }
+        let instruction = match Instruction::at(program) {
+            Some(instruction) => instruction,
+            None => return,
+        };
+
+        let ip = program.ip();
+        *self.instructions.entry(ip).or_insert(0) += 1;
+
+        let taken = match instruction.op {
+            Operation::JumpIfTrue => instruction.value(0, program) != 0,
+            Operation::JumpIfFalse => instruction.value(0, program) == 0,
+            _ => return,
+        };
+        let branch = self.branches.entry(ip).or_default();
+        if taken {
+            branch.taken += 1;
+        } else {
+            branch.not_taken += 1;
+        }
+    }
+
+    pub fn step(&mut self, program: &mut Intcode) -> Option<Signal> {
+        self.record(program);
+        program.step()
+    }
+
+    pub fn exec(&mut self, program: &mut Intcode) -> Signal {
+        loop {
+            if let Some(signal) = self.step(program) {
+                return signal;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        for (&addr, &n) in other.instructions.iter() {
+            *self.instructions.entry(addr).or_insert(0) += n;
+        }
+        for (&addr, branch) in other.branches.iter() {
+            let merged = self.branches.entry(addr).or_default();
+            merged.taken += branch.taken;
+            merged.not_taken += branch.not_taken;
+        }
+    }
+
+    // Disassembles the program with the execution count of every instruction, `-` for the ones
+    // never executed, and the outcomes of every conditional jump. Ends with a summary line of
+    // covered instructions and branch outcomes.
+    pub fn annotate(&self, codes: &[i128]) -> String {
+        let mut lines = vec![];
+        let (mut instructions, mut covered) = (0, 0);
+        let (mut outcomes, mut outcomes_covered) = (0, 0);
+
+        for line in disassemble(codes) {
+            let count = match (&line.instruction, self.instructions.get(&line.addr)) {
+                (None, _) => String::new(),
+                (Some(_), None) => "-".to_string(),
+                (Some(_), Some(n)) => n.to_string(),
+            };
+            if line.instruction.is_some() {
+                instructions += 1;
+                covered += self.instructions.contains_key(&line.addr) as usize;
+            }
+
+            let mut text = format!("{:>8}  {}", count, line);
+            let conditional = matches!(
+                line.instruction.as_ref().map(|instruction| instruction.op),
+                Some(Operation::JumpIfTrue | Operation::JumpIfFalse)
+            );
+            if conditional {
+                let branch = self.branches.get(&line.addr).copied().unwrap_or_default();
+                outcomes += 2;
+                outcomes_covered += (branch.taken > 0) as usize + (branch.not_taken > 0) as usize;
+                text = format!(
+                    "{:<60}  taken {}, not taken {}",
+                    text, branch.taken, branch.not_taken
+                );
+            }
+            lines.push(text.trim_end().to_string());
+        }
+
+        lines.push(format!(
+            "instructions {}/{}, branches {}/{}",
+            covered, instructions, outcomes_covered, outcomes
+        ));
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotate() {
+        // Outputs 1 if the input is non-zero, else 0.
+        let code = "3,13,1005,13,10,104,0,1105,1,12,104,1,99";
+        let codes = Intcode::parse(code);
+        let mut coverage = Coverage::new();
+        let mut program = Intcode::new(code, vec![0]);
+        coverage.exec(&mut program);
+        assert_eq!(vec![0], program.output);
+        assert_eq!(
+            Some(&Branch {
+                taken: 0,
+                not_taken: 1
+            }),
+            coverage.branches.get(&2)
+        );
+
+        assert!(coverage.annotate(&codes).contains("       -     10  104,1"));
+
+        let mut program = Intcode::new(code, vec![5]);
+        let mut other = Coverage::new();
+        other.exec(&mut program);
+        coverage.merge(&other);
+
+        assert_eq!(
+            "       2      0  3,13                      in [13]
+       2      2  1005,13,10                jnz [13], 10       taken 1, not taken 1
+       1      5  104,0                     out 0
+       1      7  1105,1,12                 jnz 1, 12          taken 1, not taken 0
+       1     10  104,1                     out 1
+       2     12  99                        hlt
+instructions 6/6, branches 3/4
+",
+            coverage.annotate(&codes)
+        );
+    }
+}
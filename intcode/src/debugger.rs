@@ -0,0 +1,130 @@
+use crate::callgraph::CallGraph;
+use crate::{Intcode, Signal};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pause {
+    // The next instruction is at a breakpoint.
+    Breakpoint(usize),
+    Waiting,
+    Halted,
+}
+
+// Why the debugger paused, with the backtrace at that point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stop {
+    pub pause: Pause,
+    pub backtrace: Vec<String>,
+}
+
+// Runs a machine up to breakpoints, tracking calls so that a backtrace can be shown whenever it
+// pauses.
+pub struct Debugger {
+    pub program: Intcode,
+    breakpoints: BTreeSet<usize>,
+    calls: CallGraph,
+}
+
+impl Debugger {
+    pub fn new(program: Intcode) -> Self {
+        Debugger {
+            program,
+            breakpoints: BTreeSet::new(),
+            calls: CallGraph::new(),
+        }
+    }
+
+    pub fn break_at(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn clear(&mut self, addr: usize) {
+        self.breakpoints.remove(&addr);
+    }
+
+    pub fn calls(&self) -> &CallGraph {
+        &self.calls
+    }
+
+    pub fn step(&mut self) -> Option<Signal> {
+        self.calls.step(&mut self.program)
+    }
+
+    // Runs until the machine reaches a breakpoint, waits for input or halts. Always executes at
+    // least one instruction, so calling it again moves past the breakpoint it stopped at.
+    pub fn cont(&mut self) -> Stop {
+        let pause = loop {
+            match self.step() {
+                Some(Signal::Waiting) => break Pause::Waiting,
+                Some(Signal::Halt) => break Pause::Halted,
+                None if self.breakpoints.contains(&self.program.ip()) => {
+                    break Pause::Breakpoint(self.program.ip())
+                }
+                None => {}
+            }
+        };
+        Stop {
+            pause,
+            backtrace: self.backtrace(),
+        }
+    }
+
+    // See `CallGraph::backtrace`.
+    pub fn backtrace(&self) -> Vec<String> {
+        self.calls.backtrace(self.program.ip())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints() {
+        // Outputs 3, 2, 1.
+        let mut debugger = Debugger::new(Intcode::new("104,3,104,2,104,1,3,0,99", vec![]));
+        debugger.break_at(4);
+        debugger.break_at(8);
+
+        let stop = debugger.cont();
+        assert_eq!(Pause::Breakpoint(4), stop.pause);
+        assert_eq!(vec![3, 2], debugger.program.output);
+        assert_eq!(vec!["#0  ip 4 in fn 0"], stop.backtrace);
+        assert_eq!(stop.backtrace, debugger.backtrace());
+
+        debugger.clear(8);
+        assert_eq!(Pause::Waiting, debugger.cont().pause);
+        debugger.program.input.push(0);
+        assert_eq!(Pause::Halted, debugger.cont().pause);
+    }
+
+    #[test]
+    fn day09() {
+        // The BOOST program's part 2 calls a recursive function at 922 from 912, which calls
+        // itself from 939.
+        let input = include_str!("../../day09/src/input.txt").trim();
+        let mut debugger = Debugger::new(Intcode::new(input, vec![2]));
+        debugger.break_at(922);
+
+        let stop = debugger.cont();
+        assert_eq!(Pause::Breakpoint(922), stop.pause);
+        assert_eq!(
+            vec!["#0  ip 922 in fn 922", "#1  ip 912 in fn 0"],
+            stop.backtrace
+        );
+
+        for depth in 2..5 {
+            let stop = debugger.cont();
+            assert_eq!(Pause::Breakpoint(922), stop.pause);
+            assert_eq!(depth + 1, stop.backtrace.len());
+            assert_eq!("#1  ip 939 in fn 922", stop.backtrace[1]);
+            assert_eq!(format!("#{}  ip 912 in fn 0", depth), stop.backtrace[depth]);
+        }
+
+        debugger.clear(922);
+        let stop = debugger.cont();
+        assert_eq!(Pause::Halted, stop.pause);
+        assert_eq!(vec!["#0  ip 921 in fn 0"], stop.backtrace);
+        assert_eq!(vec![80379], debugger.program.output);
+    }
+}
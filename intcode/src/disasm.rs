@@ -0,0 +1,77 @@
+use crate::Instruction;
+use std::fmt;
+
+// A decoded instruction, or a single cell that does not decode as one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub addr: usize,
+    pub cells: Vec<i128>,
+    pub instruction: Option<Instruction>,
+}
+
+// Prints `    2  1,225,6,6           add [225], [6], [6]`.
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells = self
+            .cells
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(",");
+        match &self.instruction {
+            Some(instruction) => write!(f, "{:>5}  {:<24}  {}", self.addr, cells, instruction),
+            None => write!(f, "{:>5}  {:<24}  data", self.addr, cells),
+        }
+    }
+}
+
+// Decodes the program with a linear sweep from address 0. Data mixed into the code may decode as
+// instructions, since the sweep does not follow jumps.
+pub fn disassemble(codes: &[i128]) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut addr = 0;
+
+    while addr < codes.len() {
+        let line = match Instruction::decode(codes, addr) {
+            Some(instruction) if addr + instruction.size() <= codes.len() => Line {
+                addr,
+                cells: codes[addr..addr + instruction.size()].to_vec(),
+                instruction: Some(instruction),
+            },
+            _ => Line {
+                addr,
+                cells: vec![codes[addr]],
+                instruction: None,
+            },
+        };
+        addr += line.cells.len();
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_sweep() {
+        let lines = disassemble(&[3, 225, 1, 225, 6, 6, 1100, 204, -1, 99, 1]);
+        let text = lines
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            vec![
+                "    0  3,225                     in [225]",
+                "    2  1,225,6,6                 add [225], [6], [6]",
+                "    6  1100                      data",
+                "    7  204,-1                    out [rb-1]",
+                "    9  99                        hlt",
+                "   10  1                         data",
+            ],
+            text
+        );
+    }
+}
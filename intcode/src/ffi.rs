@@ -0,0 +1,263 @@
+// C API over `Intcode`. Values cross the boundary as 64-bit integers. include/intcode.h is
+// generated from the signatures in this file, see `tests::header`.
+use crate::{Intcode, Signal, State};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+pub const INTCODE_ERROR: i32 = -1;
+pub const INTCODE_RUNNING: i32 = 0;
+pub const INTCODE_WAITING: i32 = 1;
+pub const INTCODE_HALTED: i32 = 2;
+
+/// Creates a machine from `len` values. Returns NULL if `codes` is NULL.
+///
+/// # Safety
+///
+/// `codes` must point to `len` readable values.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_new(codes: *const i64, len: usize) -> *mut Intcode {
+    if codes.is_null() {
+        return std::ptr::null_mut();
+    }
+    let codes = slice::from_raw_parts(codes, len);
+    let program = Intcode::from(codes.iter().map(|&n| n as i128).collect::<Vec<i128>>());
+    Box::into_raw(Box::new(program))
+}
+
+/// Queues a value for the next input instruction.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_push_input(program: *mut Intcode, value: i64) {
+    (*program).input.push(value as i128);
+}
+
+/// Runs until the machine waits for input or halts, and returns INTCODE_WAITING or
+/// INTCODE_HALTED. Returns INTCODE_ERROR if the program is invalid.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(program: *mut Intcode) -> i32 {
+    let program = &mut *program;
+    match panic::catch_unwind(AssertUnwindSafe(|| program.exec())) {
+        Ok(Signal::Waiting) => INTCODE_WAITING,
+        Ok(Signal::Halt) => INTCODE_HALTED,
+        Err(_) => INTCODE_ERROR,
+    }
+}
+
+/// Takes the oldest output into `value`. Returns 1 if there was one, 0 if there are no outputs,
+/// or INTCODE_ERROR if it does not fit in 64 bits, in which case it stays queued.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed, and `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_pop_output(program: *mut Intcode, value: *mut i64) -> i32 {
+    let program = &mut *program;
+    let n = match program.output.first() {
+        Some(&n) => n,
+        None => return 0,
+    };
+    match i64::try_from(n) {
+        Ok(n) => {
+            program.output.remove(0);
+            *value = n;
+            1
+        }
+        Err(_) => INTCODE_ERROR,
+    }
+}
+
+/// Returns INTCODE_RUNNING, INTCODE_WAITING or INTCODE_HALTED.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_state(program: *const Intcode) -> i32 {
+    match (*program).state() {
+        State::Running => INTCODE_RUNNING,
+        State::Waiting => INTCODE_WAITING,
+        State::Halted => INTCODE_HALTED,
+    }
+}
+
+/// Returns the instruction pointer.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_ip(program: *const Intcode) -> usize {
+    (*program).ip()
+}
+
+/// Returns the value at `addr`, truncated to 64 bits.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_peek(program: *const Intcode, addr: usize) -> i64 {
+    (*program).peek(addr) as i64
+}
+
+/// Returns the number of instructions executed so far.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_steps(program: *const Intcode) -> u64 {
+    (*program).steps() as u64
+}
+
+/// Frees the machine. Does nothing if `program` is NULL.
+///
+/// # Safety
+///
+/// `program` must come from `intcode_new` and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(program: *mut Intcode) {
+    if !program.is_null() {
+        drop(Box::from_raw(program));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn c_type(rust: &str) -> String {
+        match rust.trim() {
+            "*mut Intcode" => "Intcode *".to_string(),
+            "*const Intcode" => "const Intcode *".to_string(),
+            "*const i64" => "const int64_t *".to_string(),
+            "*mut i64" => "int64_t *".to_string(),
+            "i64" => "int64_t".to_string(),
+            "i32" => "int32_t".to_string(),
+            "u64" => "uint64_t".to_string(),
+            "usize" => "size_t".to_string(),
+            t => panic!("no C type for {}", t),
+        }
+    }
+
+    // Builds the header from the constants, signatures and first doc paragraph of this file.
+    fn generate() -> String {
+        let source = include_str!("ffi.rs");
+        let source = &source[..source.find("#[cfg(test)]").unwrap()];
+        let mut header = vec![
+            "/* Generated from intcode/src/ffi.rs by `INTCODE_BLESS=1 cargo test`. */".to_string(),
+            "#ifndef INTCODE_H".to_string(),
+            "#define INTCODE_H".to_string(),
+            String::new(),
+            "#include <stddef.h>".to_string(),
+            "#include <stdint.h>".to_string(),
+            String::new(),
+            "typedef struct Intcode Intcode;".to_string(),
+            String::new(),
+        ];
+
+        for line in source.lines() {
+            if let Some(constant) = line.strip_prefix("pub const ") {
+                let (name, value) = constant.split_once(": i32 = ").unwrap();
+                header.push(format!("#define {} {}", name, value.trim_end_matches(';')));
+            }
+        }
+
+        let mut doc = vec![];
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            if let Some(comment) = line.strip_prefix("///") {
+                doc.push(comment.trim().to_string());
+                continue;
+            }
+            if !line.starts_with("pub unsafe extern \"C\" fn ") {
+                continue;
+            }
+
+            let mut signature = line.to_string();
+            while !signature.contains('{') {
+                signature.push_str(lines.next().unwrap().trim());
+            }
+            let signature = signature.trim_start_matches("pub unsafe extern \"C\" fn ");
+            let (name, rest) = signature.split_once('(').unwrap();
+            let (params, rest) = rest.split_once(')').unwrap();
+            let ret = match rest.split_once("->") {
+                Some((_, ret)) => c_type(ret.trim_end_matches('{')),
+                None => "void".to_string(),
+            };
+            let params = params
+                .split(',')
+                .filter(|param| !param.trim().is_empty())
+                .map(|param| {
+                    let (name, rust) = param.split_once(':').unwrap();
+                    let c = c_type(rust);
+                    let sep = if c.ends_with('*') { "" } else { " " };
+                    format!("{}{}{}", c, sep, name.trim())
+                })
+                .collect::<Vec<String>>();
+
+            let summary = doc.iter().take_while(|line| !line.is_empty()).cloned();
+            header.push(String::new());
+            header.push(format!(
+                "/* {} */",
+                summary.collect::<Vec<String>>().join(" ")
+            ));
+            let sep = if ret.ends_with('*') { "" } else { " " };
+            header.push(format!("{}{}{}({});", ret, sep, name, params.join(", ")));
+            doc.clear();
+        }
+
+        header.push(String::new());
+        header.push("#endif".to_string());
+        header.join("\n") + "\n"
+    }
+
+    #[test]
+    fn header() {
+        let header = generate();
+        if env::var("INTCODE_BLESS").is_ok() {
+            fs::write(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/include/intcode.h"),
+                &header,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            include_str!("../include/intcode.h"),
+            header,
+            "run `INTCODE_BLESS=1 cargo test` to regenerate the header"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        unsafe {
+            let codes = [3, 0, 4, 0, 99];
+            let program = intcode_new(codes.as_ptr(), codes.len());
+            assert_eq!(INTCODE_WAITING, intcode_run(program));
+            assert_eq!(INTCODE_WAITING, intcode_state(program));
+            intcode_push_input(program, 42);
+            assert_eq!(INTCODE_HALTED, intcode_run(program));
+
+            let mut value = 0;
+            assert_eq!(1, intcode_pop_output(program, &mut value));
+            assert_eq!(42, value);
+            assert_eq!(0, intcode_pop_output(program, &mut value));
+            assert_eq!(4, intcode_ip(program));
+            assert_eq!(42, intcode_peek(program, 0));
+            assert_eq!(2, intcode_steps(program));
+            intcode_free(program);
+
+            assert!(intcode_new(std::ptr::null(), 0).is_null());
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::Outputs;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+// A message made of a fixed number of outputs, e.g. day13's (x, y, tile_id) triples.
+pub trait Frame: Sized {
+    const WIDTH: usize;
+
+    // Decodes exactly `WIDTH` values, or explains why they are not a valid message.
+    fn decode(values: &[i128]) -> Result<Self, String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameError {
+    // The outputs ended in the middle of a frame.
+    Partial(Vec<i128>),
+    // A complete frame that `Frame::decode` rejected.
+    Invalid { values: Vec<i128>, reason: String },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Partial(values) => write!(f, "partial frame {:?}", values),
+            FrameError::Invalid { values, reason } => {
+                write!(f, "invalid frame {:?}: {}", values, reason)
+            }
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+fn decode_frame<F: Frame>(values: Vec<i128>) -> Result<F, FrameError> {
+    if values.len() < F::WIDTH {
+        return Err(FrameError::Partial(values));
+    }
+    F::decode(&values).map_err(|reason| FrameError::Invalid { values, reason })
+}
+
+// Decodes outputs that have already been collected, e.g. `Intcode::output` after `exec`.
+pub fn decode<F: Frame>(values: &[i128]) -> Result<Vec<F>, FrameError> {
+    values
+        .chunks(F::WIDTH)
+        .map(|values| decode_frame(values.to_vec()))
+        .collect()
+}
+
+// Iterator over the messages of a running machine, see `Outputs::frames`.
+pub struct Frames<'a, F> {
+    outputs: Outputs<'a>,
+    frame: PhantomData<F>,
+}
+
+impl<'a, F: Frame> Frames<'a, F> {
+    pub(crate) fn new(outputs: Outputs<'a>) -> Self {
+        Frames {
+            outputs,
+            frame: PhantomData,
+        }
+    }
+}
+
+impl<F: Frame> Iterator for Frames<'_, F> {
+    type Item = Result<F, FrameError>;
+
+    fn next(&mut self) -> Option<Result<F, FrameError>> {
+        let values = self.outputs.by_ref().take(F::WIDTH).collect::<Vec<i128>>();
+        if values.is_empty() {
+            None
+        } else {
+            Some(decode_frame(values))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Intcode;
+
+    // day23's packets, with 255 reserved for the NAT.
+    #[derive(Debug, PartialEq)]
+    enum Packet {
+        Send { address: usize, x: i128, y: i128 },
+        Nat { x: i128, y: i128 },
+    }
+
+    impl Frame for Packet {
+        const WIDTH: usize = 3;
+
+        fn decode(values: &[i128]) -> Result<Self, String> {
+            match *values {
+                [255, x, y] => Ok(Packet::Nat { x, y }),
+                [address @ 0..=49, x, y] => Ok(Packet::Send {
+                    address: address as usize,
+                    x,
+                    y,
+                }),
+                [address, ..] => Err(format!("no computer at address {}", address)),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let mut program = Intcode::new("104,3,104,1,104,2,104,255,104,4,104,5,99", vec![]);
+        let packets = program
+            .outputs()
+            .frames::<Packet>()
+            .collect::<Result<Vec<Packet>, FrameError>>();
+        assert_eq!(
+            Ok(vec![
+                Packet::Send {
+                    address: 3,
+                    x: 1,
+                    y: 2
+                },
+                Packet::Nat { x: 4, y: 5 }
+            ]),
+            packets
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut program = Intcode::new("104,60,104,1,104,2,104,3,104,4,99", vec![]);
+        let packets = program
+            .outputs()
+            .frames::<Packet>()
+            .collect::<Vec<Result<Packet, FrameError>>>();
+        assert_eq!(
+            vec![
+                Err(FrameError::Invalid {
+                    values: vec![60, 1, 2],
+                    reason: "no computer at address 60".to_string()
+                }),
+                Err(FrameError::Partial(vec![3, 4])),
+            ],
+            packets
+        );
+        assert_eq!(
+            "partial frame [3, 4]",
+            FrameError::Partial(vec![3, 4]).to_string()
+        );
+
+        assert_eq!(
+            Err(FrameError::Partial(vec![7])),
+            decode::<Packet>(&[1, 2, 3, 7])
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{Instruction, Intcode, Operation, ParameterMode, Signal, State};
+
+// Per-address access counts, indexed by address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Counts {
+    pub reads: Vec<usize>,
+    pub writes: Vec<usize>,
+    pub executes: Vec<usize>,
+}
+
+impl Counts {
+    fn bump(counts: &mut Vec<usize>, addr: usize) {
+        if addr >= counts.len() {
+            counts.resize(addr + 1, 0);
+        }
+        counts[addr] += 1;
+    }
+
+    // Number of addresses that have been accessed in any way, up to the highest one.
+    pub fn len(&self) -> usize {
+        self.reads
+            .len()
+            .max(self.writes.len())
+            .max(self.executes.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Renders one pixel per address in rows of `width`, as a binary PPM. Writes are red, executed
+    // instruction cells green and reads blue, so code shows up green, data blue and the stack
+    // written through the relative base magenta. Brightness grows with the log of the count.
+    pub fn ppm(&self, width: usize) -> Vec<u8> {
+        let width = width.max(1);
+        let height = self.len().div_ceil(width).max(1);
+        let max = |counts: &Vec<usize>| counts.iter().copied().max().unwrap_or(0);
+        let (reads, writes, executes) = (max(&self.reads), max(&self.writes), max(&self.executes));
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for addr in 0..width * height {
+            image.push(shade(&self.writes, addr, writes));
+            image.push(shade(&self.executes, addr, executes));
+            image.push(shade(&self.reads, addr, reads));
+        }
+        image
+    }
+}
+
+fn shade(counts: &[usize], addr: usize, max: usize) -> u8 {
+    match counts.get(addr) {
+        None | Some(0) => 0,
+        Some(&n) => (64.0 + 191.0 * (n as f64).ln_1p() / (max as f64).ln_1p()) as u8,
+    }
+}
+
+// Records which addresses a run reads, writes and executes. Drive the machine through `step` or
+// `exec` instead of the machine's own.
+#[derive(Debug, Clone, Default)]
+pub struct Heatmap {
+    counts: Counts,
+    every: Option<usize>,
+    steps: usize,
+    frames: Vec<Counts>,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Heatmap::default()
+    }
+
+    // Keeps a snapshot of the counts every `steps` instructions, for `frames`.
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = Some(steps.max(1));
+        self
+    }
+
+    pub fn counts(&self) -> &Counts {
+        &self.counts
+    }
+
+    // Records the accesses of the next instruction, unless the machine is waiting for input.
+    pub fn record(&mut self, program: &Intcode) {
+        if program.state() == State::Waiting {
+            return;
+        }
+        let instruction = match Instruction::at(program) {
+            Some(instruction) => instruction,
+            None => return,
+        };
+
+        let ip = program.ip();
+        for addr in ip..ip + instruction.size() {
+            Counts::bump(&mut self.counts.executes, addr);
+        }
+
+        let written = instruction.op.writes();
+        for (pos, &(mode, n)) in instruction.params.iter().enumerate() {
+            let addr = match mode {
+                ParameterMode::Position => n,
+                ParameterMode::Immediate => continue,
+                ParameterMode::Relative => n + program.relative_base(),
+            };
+            if addr < 0 {
+                continue;
+            }
+            if written == Some(pos + 1) {
+                Counts::bump(&mut self.counts.writes, addr as usize);
+            } else {
+                Counts::bump(&mut self.counts.reads, addr as usize);
+            }
+        }
+
+        // Halting is not a step, so it does not start a frame.
+        if instruction.op == Operation::Halt {
+            return;
+        }
+        self.steps += 1;
+        if let Some(every) = self.every {
+            if self.steps.is_multiple_of(every) {
+                self.frames.push(self.counts.clone());
+            }
+        }
+    }
+
+    pub fn step(&mut self, program: &mut Intcode) -> Option<Signal> {
+        self.record(program);
+        program.step()
+    }
+
+    pub fn exec(&mut self, program: &mut Intcode) -> Signal {
+        loop {
+            if let Some(signal) = self.step(program) {
+                return signal;
+            }
+        }
+    }
+
+    // The snapshots taken so far followed by the current counts, each rendered with `Counts::ppm`.
+    // Every frame is laid out over the final address range, so the frames line up.
+    pub fn frames(&self, width: usize) -> Vec<Vec<u8>> {
+        let len = self.counts.len();
+        self.frames
+            .iter()
+            .chain(std::iter::once(&self.counts))
+            .map(|counts| {
+                let mut counts = counts.clone();
+                counts.executes.resize(len.max(counts.executes.len()), 0);
+                counts.ppm(width)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        // Adds the input to itself through the relative base, then outputs it.
+        let mut program = Intcode::new("109,20,203,0,22201,0,0,1,204,1,99", vec![21]);
+        let mut heatmap = Heatmap::new().every(2);
+        assert_eq!(Signal::Halt, heatmap.exec(&mut program));
+        assert_eq!(vec![42], program.output);
+
+        let counts = heatmap.counts();
+        assert_eq!(vec![1; 11], counts.executes);
+        assert_eq!(1, counts.writes[20]);
+        assert_eq!(1, counts.writes[21]);
+        assert_eq!(2, counts.reads[20], "both operands of the add");
+        assert_eq!(1, counts.reads[21]);
+        assert_eq!(22, counts.len());
+
+        let image = counts.ppm(4);
+        assert!(image.starts_with(b"P6\n4 6\n255\n"));
+        assert_eq!(11 + 4 * 6 * 3, image.len());
+        let pixel = |addr: usize| &image[11 + addr * 3..11 + addr * 3 + 3];
+        assert_eq!(&[0, 255, 0], pixel(0), "executed once");
+        assert_eq!(&[255, 0, 255], pixel(20));
+        assert_eq!(&[255, 0, 184], pixel(21), "read half as often as 20");
+        assert_eq!(&[0, 0, 0], pixel(22));
+
+        // Four instructions, a snapshot after the second and fourth, and the final counts.
+        let frames = heatmap.frames(4);
+        assert_eq!(3, frames.len());
+        assert!(frames.iter().all(|frame| frame.len() == image.len()));
+        assert_eq!(&image, frames.last().unwrap());
+    }
+
+    #[test]
+    fn waiting_is_not_recorded() {
+        let mut program = Intcode::new("3,0,99", vec![]);
+        let mut heatmap = Heatmap::new();
+        assert_eq!(Signal::Waiting, heatmap.exec(&mut program));
+        assert!(heatmap.counts().is_empty());
+    }
+}
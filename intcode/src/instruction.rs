@@ -0,0 +1,138 @@
+use crate::{Intcode, Operation, ParameterMode};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub op: Operation,
+    pub params: Vec<(ParameterMode, i128)>,
+}
+
+impl Instruction {
+    // Decodes the instruction at `ip` the same way `Intcode::exec` would, returning None when the
+    // cell does not hold a valid opcode or parameter mode.
+    pub fn decode(codes: &[i128], ip: usize) -> Option<Instruction> {
+        Instruction::decode_with(|addr| codes.get(addr).copied(), ip)
+    }
+
+    // Decodes the next instruction of a running machine.
+    pub fn at(program: &Intcode) -> Option<Instruction> {
+        Instruction::decode_with(|addr| Some(program.peek(addr)), program.ip())
+    }
+
+    fn decode_with<F>(read: F, ip: usize) -> Option<Instruction>
+    where
+        F: Fn(usize) -> Option<i128>,
+    {
+        let code = read(ip)?;
+        if code < 0 {
+            return None;
+        }
+
+        let op = Operation::parse(code % 100)?;
+        let mut modes = code / 100;
+        let mut params = Vec::with_capacity(op.arity());
+        for pos in 1..=op.arity() {
+            let mode = ParameterMode::parse(modes % 10)?;
+            modes /= 10;
+            params.push((mode, read(ip + pos).unwrap_or(0)));
+        }
+
+        Some(Instruction { op, params })
+    }
+
+    // Value of the parameter at `pos` (0-based) on the live machine, as the next step would read
+    // it. Addresses the machine would reject read as 0.
+    pub fn value(&self, pos: usize, program: &Intcode) -> i128 {
+        let (mode, n) = self.params[pos];
+        let addr = match mode {
+            ParameterMode::Position => n,
+            ParameterMode::Immediate => return n,
+            ParameterMode::Relative => n + program.relative_base(),
+        };
+        if addr < 0 {
+            0
+        } else {
+            program.peek(addr as usize)
+        }
+    }
+
+    // Number of cells occupied by the instruction, including the opcode.
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    // Encodes the instruction back into its opcode and parameters.
+    pub fn encode(&self) -> Vec<i128> {
+        let mut opcode = self.op as i128;
+        let mut scale = 100;
+        for (mode, _) in self.params.iter() {
+            opcode += *mode as i128 * scale;
+            scale *= 10;
+        }
+
+        let mut codes = vec![opcode];
+        codes.extend(self.params.iter().map(|(_, n)| n));
+        codes
+    }
+}
+
+// Prints `add 1, [225], [rb+6]`: position mode in brackets, relative mode offset from the base.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mnemonic = match self.op {
+            Operation::Add => "add",
+            Operation::Mul => "mul",
+            Operation::Input => "in",
+            Operation::Output => "out",
+            Operation::JumpIfTrue => "jnz",
+            Operation::JumpIfFalse => "jz",
+            Operation::LessThan => "lt",
+            Operation::Equal => "eq",
+            Operation::RelativeBase => "arb",
+            Operation::Halt => "hlt",
+        };
+        let params = self
+            .params
+            .iter()
+            .map(|&(mode, n)| match mode {
+                ParameterMode::Position => format!("[{}]", n),
+                ParameterMode::Immediate => n.to_string(),
+                ParameterMode::Relative if n < 0 => format!("[rb{}]", n),
+                ParameterMode::Relative => format!("[rb+{}]", n),
+            })
+            .collect::<Vec<String>>();
+        if params.is_empty() {
+            write!(f, "{}", mnemonic)
+        } else {
+            write!(f, "{} {}", mnemonic, params.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let codes = vec![1002, 4, 3, 4, 33];
+        let instruction = Instruction::decode(&codes, 0).unwrap();
+        assert_eq!(Operation::Mul, instruction.op);
+        assert_eq!(
+            vec![
+                (ParameterMode::Position, 4),
+                (ParameterMode::Immediate, 3),
+                (ParameterMode::Position, 4)
+            ],
+            instruction.params
+        );
+        assert_eq!(4, instruction.size());
+        assert_eq!(vec![1002, 4, 3, 4], instruction.encode());
+
+        assert_eq!("mul [4], 3, [4]", instruction.to_string());
+
+        assert_eq!(None, Instruction::decode(&codes, 4), "invalid opcode");
+        assert_eq!(None, Instruction::decode(&[-1], 0), "negative opcode");
+        assert_eq!(None, Instruction::decode(&[301], 0), "invalid mode");
+    }
+}
@@ -1,18 +1,41 @@
+pub mod batch;
+pub mod callgraph;
+pub mod corpus;
+pub mod coverage;
+pub mod debugger;
+pub mod disasm;
+pub mod ffi;
+pub mod frame;
+pub mod heatmap;
+mod instruction;
+mod memory;
 mod operation;
+pub mod optimizer;
+mod outputs;
 mod parameter_mode;
+#[cfg(feature = "async")]
+pub mod runtime;
+pub mod scheduler;
+pub mod session;
 mod signal;
+mod state;
 
-use operation::Operation;
-use parameter_mode::ParameterMode;
+pub use instruction::Instruction;
+pub use memory::Memory;
+pub use operation::Operation;
+pub use outputs::{Chunks, Outputs};
+pub use parameter_mode::ParameterMode;
 pub use signal::Signal;
+pub use state::State;
 
 #[derive(Debug, Clone)]
 pub struct Intcode {
     base: i128,
     pub input: Vec<i128>,
-    pub ip: usize,
-    pub codes: Vec<i128>,
+    ip: usize,
+    codes: Memory,
     pub output: Vec<i128>,
+    steps: usize,
 }
 
 fn get_mode(mode: i128, pos: usize) -> ParameterMode {
@@ -31,15 +54,52 @@ impl Intcode {
     }
 
     pub fn new(code: &str, input: Vec<i128>) -> Self {
-        Intcode {
-            input: input,
-            ip: 0,
-            codes: Intcode::parse(code),
-            base: 0,
-            output: Vec::new(),
+        let mut program = Intcode::from(Intcode::parse(code));
+        program.input = input;
+        program
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> i128 {
+        self.base
+    }
+
+    // Reads any address. Cells that were never written are zero.
+    pub fn peek(&self, addr: usize) -> i128 {
+        self.codes.get(addr)
+    }
+
+    // Writes any address, growing the memory if needed.
+    pub fn poke(&mut self, addr: usize, n: i128) {
+        self.codes.set(addr, n)
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.codes
+    }
+
+    // Number of instructions executed so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn state(&self) -> State {
+        match Operation::parse(self.peek(self.ip) % 100) {
+            Some(Operation::Halt) => State::Halted,
+            Some(Operation::Input) if self.input.is_empty() => State::Waiting,
+            _ => State::Running,
         }
     }
 
+    // Copies the machine. The memory is shared with the original until either of them writes to
+    // it, so forking is cheap even for large programs.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
     pub fn set_input(&mut self, input: i128) {
         self.input.insert(0, input);
     }
@@ -66,90 +126,91 @@ impl Intcode {
     }
 
     fn read(&mut self, ip: usize) -> i128 {
-        if ip >= self.codes.len() {
-            0
-            //self.codes.resize(ip + 1, 0);
-        } else {
-            self.codes[ip as usize]
-        }
+        self.codes.get(ip)
     }
 
     fn write(&mut self, ip: usize, n: i128) {
-        if ip >= self.codes.len() {
-            self.codes.resize(ip + 1, 0);
-        }
-        self.codes[ip] = n
+        self.codes.set(ip, n)
     }
 
     fn opcode(&mut self) -> Operation {
         let n = self.codes[self.ip] % 100;
-        let op = Operation::from(n);
-        op
+        Operation::from(n)
+    }
+
+    // Executes a single instruction. Returns the signal when the machine cannot make progress,
+    // without moving the instruction pointer.
+    pub fn step(&mut self) -> Option<Signal> {
+        match self.opcode() {
+            Operation::Add => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                let c = self.get_parameter(3, 'w');
+                self.write(c as usize, a + b);
+                self.ip += 4;
+            }
+            Operation::Mul => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                let c = self.get_parameter(3, 'w');
+                self.write(c as usize, a * b);
+                self.ip += 4;
+            }
+            Operation::Input => {
+                if self.input.is_empty() {
+                    return Some(Signal::Waiting);
+                }
+                let a = self.get_parameter(1, 'w');
+                let n = self.input.remove(0);
+                self.write(a as usize, n);
+                self.ip += 2;
+            }
+            Operation::Output => {
+                let a = self.get_parameter(1, 'r');
+                self.output.push(a);
+                self.ip += 2;
+            }
+            Operation::JumpIfTrue => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                self.ip = if a != 0 { b as usize } else { self.ip + 3 }
+            }
+            Operation::JumpIfFalse => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                self.ip = if a == 0 { b as usize } else { self.ip + 3 }
+            }
+            Operation::LessThan => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                let c = self.get_parameter(3, 'w');
+                self.write(c as usize, if a < b { 1 } else { 0 });
+                self.ip += 4;
+            }
+            Operation::Equal => {
+                let a = self.get_parameter(1, 'r');
+                let b = self.get_parameter(2, 'r');
+                let c = self.get_parameter(3, 'w');
+                self.write(c as usize, if a == b { 1 } else { 0 });
+                self.ip += 4;
+            }
+            Operation::RelativeBase => {
+                let a = self.get_parameter(1, 'r');
+                self.base += a;
+                self.ip += 2;
+            }
+            Operation::Halt => {
+                return Some(Signal::Halt);
+            }
+        }
+        self.steps += 1;
+        None
     }
 
     pub fn exec(&mut self) -> Signal {
         loop {
-            match self.opcode() {
-                Operation::Add => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    let c = self.get_parameter(3, 'w');
-                    self.write(c as usize, a + b);
-                    self.ip += 4;
-                }
-                Operation::Mul => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    let c = self.get_parameter(3, 'w');
-                    self.write(c as usize, a * b);
-                    self.ip += 4;
-                }
-                Operation::Input => {
-                    if self.input.is_empty() {
-                        return Signal::Waiting;
-                    }
-                    let a = self.get_parameter(1, 'w');
-                    let n = self.input.remove(0);
-                    self.write(a as usize, n);
-                    self.ip += 2;
-                }
-                Operation::Output => {
-                    let a = self.get_parameter(1, 'r');
-                    self.output.push(a);
-                    self.ip += 2;
-                }
-                Operation::JumpIfTrue => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    self.ip = if a != 0 { b as usize } else { self.ip + 3 }
-                }
-                Operation::JumpIfFalse => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    self.ip = if a == 0 { b as usize } else { self.ip + 3 }
-                }
-                Operation::LessThan => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    let c = self.get_parameter(3, 'w');
-                    self.write(c as usize, if a < b { 1 } else { 0 });
-                    self.ip += 4;
-                }
-                Operation::Equal => {
-                    let a = self.get_parameter(1, 'r');
-                    let b = self.get_parameter(2, 'r');
-                    let c = self.get_parameter(3, 'w');
-                    self.write(c as usize, if a == b { 1 } else { 0 });
-                    self.ip += 4;
-                }
-                Operation::RelativeBase => {
-                    let a = self.get_parameter(1, 'r');
-                    self.base += a;
-                    self.ip += 2;
-                }
-                Operation::Halt => {
-                    return Signal::Halt;
-                }
+            if let Some(signal) = self.step() {
+                return signal;
             }
         }
     }
@@ -163,6 +224,10 @@ impl Intcode {
         }
         self.output.clone()
     }
+
+    pub fn outputs(&mut self) -> Outputs<'_> {
+        Outputs::new(self)
+    }
 }
 
 impl From<Vec<i128>> for Intcode {
@@ -170,22 +235,17 @@ impl From<Vec<i128>> for Intcode {
         Intcode {
             input: Vec::new(),
             ip: 0,
-            codes: codes,
+            codes: Memory::from(codes),
             base: 0,
             output: Vec::new(),
+            steps: 0,
         }
     }
 }
 
 impl From<String> for Intcode {
     fn from(codes: String) -> Self {
-        Intcode {
-            input: Vec::new(),
-            ip: 0,
-            codes: Intcode::parse(codes.trim().into()),
-            base: 0,
-            output: Vec::new(),
-        }
+        Intcode::from(Intcode::parse(codes.trim()))
     }
 }
 
@@ -193,6 +253,7 @@ impl From<String> for Intcode {
 mod tests {
     use super::*;
 
+    #[test]
     fn part1() {
         let mut program = Intcode::new("3,0,4,0,99", vec![1]);
         program.run_until_halt();
@@ -220,34 +281,60 @@ mod tests {
 
         // Input 1.
         let input = "3,9,8,9,10,9,4,9,99,-1,8";
-        assert_eq!(1, exec(&input, 8), "eq8");
-        assert_eq!(0, exec(&input, 0), "neq8");
+        assert_eq!(1, exec(input, 8), "eq8");
+        assert_eq!(0, exec(input, 0), "neq8");
 
         // Input 2.
         let input = "3,9,7,9,10,9,4,9,99,-1,8";
-        assert_eq!(1, exec(&input, 0), "lt8");
-        assert_eq!(0, exec(&input, 8), "eq8");
+        assert_eq!(1, exec(input, 0), "lt8");
+        assert_eq!(0, exec(input, 8), "eq8");
 
         // Input 3.
         let input = "3,3,1108,-1,8,3,4,3,99";
-        assert_eq!(1, exec(&input, 8), "eq8");
-        assert_eq!(0, exec(&input, 0), "neq8");
+        assert_eq!(1, exec(input, 8), "eq8");
+        assert_eq!(0, exec(input, 0), "neq8");
 
         let input = "3,3,1107,-1,8,3,4,3,99";
-        assert_eq!(1, exec(&input, 0), "lt8");
-        assert_eq!(0, exec(&input, 8), "eq8");
+        assert_eq!(1, exec(input, 0), "lt8");
+        assert_eq!(0, exec(input, 8), "eq8");
 
         let input = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
-        assert_eq!(0, exec(&input, 0), "zero");
-        assert_eq!(1, exec(&input, 1), "non-zero");
+        assert_eq!(0, exec(input, 0), "zero");
+        assert_eq!(1, exec(input, 1), "non-zero");
 
         let input = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
-        assert_eq!(0, exec(&input, 0), "zero");
-        assert_eq!(1, exec(&input, 1), "non-zero");
+        assert_eq!(0, exec(input, 0), "zero");
+        assert_eq!(1, exec(input, 1), "non-zero");
 
         let input = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
-        assert_eq!(999, exec(&input, 7), "lt8");
-        assert_eq!(1000, exec(&input, 8), "eq8");
-        assert_eq!(1001, exec(&input, 9), "gt8");
+        assert_eq!(999, exec(input, 7), "lt8");
+        assert_eq!(1000, exec(input, 8), "eq8");
+        assert_eq!(1001, exec(input, 9), "gt8");
+    }
+
+    #[test]
+    fn introspection() {
+        let mut program = Intcode::new("109,5,203,10,204,10,99", vec![]);
+        assert_eq!(State::Running, program.state());
+
+        assert_eq!(None, program.step());
+        assert_eq!(5, program.relative_base());
+        assert_eq!(2, program.ip());
+        assert_eq!(State::Waiting, program.state());
+        assert_eq!(Some(Signal::Waiting), program.step());
+        assert_eq!(1, program.steps(), "waiting is not a step");
+
+        program.set_input(42);
+        assert_eq!(State::Running, program.state());
+        assert_eq!(Signal::Halt, program.exec());
+        assert_eq!(State::Halted, program.state());
+        assert_eq!(3, program.steps());
+        assert_eq!(vec![42], program.output);
+
+        assert_eq!(42, program.peek(15));
+        assert_eq!(0, program.peek(1000), "unwritten memory is zero");
+        program.poke(1000, 7);
+        assert_eq!(7, program.peek(1000));
+        assert_eq!(1001, program.memory().len());
     }
 }
@@ -0,0 +1,316 @@
+use intcode::disasm::disassemble;
+use intcode::heatmap::Heatmap;
+use intcode::{Instruction, Intcode, Operation, ParameterMode, Signal, State};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str =
+    "usage: intcode run <file> [--input 1,2,3] [--ascii] [--trace] [--max-steps n] \
+[--patch addr=value]... [--peek addr]... [--disasm] [--heatmap out.ppm [--heatmap-width n] [--heatmap-every n]]";
+
+// Exit codes.
+const HALTED: i32 = 0;
+const ERROR: i32 = 1;
+const STARVED: i32 = 2;
+const STEP_LIMIT: i32 = 3;
+
+// Highest address --patch and --peek take. Memory up to it can still be listed with --disasm.
+const MAX_ADDRESS: usize = 1 << 20;
+
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    file: String,
+    input: Vec<i128>,
+    ascii: bool,
+    trace: bool,
+    max_steps: Option<usize>,
+    patches: Vec<(usize, i128)>,
+    peeks: Vec<usize>,
+    disasm: bool,
+    heatmap: Option<String>,
+    heatmap_width: Option<usize>,
+    heatmap_every: Option<usize>,
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let code = match parse_args(&args).and_then(|options| run(&options)) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ERROR
+        }
+    };
+    process::exit(code);
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    match (args.next().map(String::as_str), args.next()) {
+        (Some("run"), Some(file)) => options.file = file.to_string(),
+        _ => return Err(USAGE.to_string()),
+    }
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--input" => options.input = parse_codes(value()?)?,
+            "--ascii" => options.ascii = true,
+            "--trace" => options.trace = true,
+            "--disasm" => options.disasm = true,
+            "--max-steps" => {
+                let n = value()?;
+                options.max_steps = Some(n.parse().map_err(|_| format!("invalid steps: {}", n))?);
+            }
+            "--heatmap" => options.heatmap = Some(value()?.to_string()),
+            "--heatmap-width" => options.heatmap_width = Some(parse_count(value()?)?),
+            "--heatmap-every" => options.heatmap_every = Some(parse_count(value()?)?),
+            "--patch" => {
+                let patch = value()?;
+                let (addr, n) = patch
+                    .split_once('=')
+                    .ok_or(format!("invalid patch: {}", patch))?;
+                let n = n.parse().map_err(|_| format!("invalid patch: {}", patch))?;
+                options.patches.push((parse_address(addr)?, n));
+            }
+            "--peek" => options.peeks.push(parse_address(value()?)?),
+            _ => return Err(format!("unknown option: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_codes(code: &str) -> Result<Vec<i128>, String> {
+    code.trim()
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .map_err(|_| format!("invalid value: {}", n))
+        })
+        .collect()
+}
+
+fn parse_address(addr: &str) -> Result<usize, String> {
+    match addr.parse() {
+        Ok(addr) if addr <= MAX_ADDRESS => Ok(addr),
+        _ => Err(format!(
+            "invalid address: {} (at most {})",
+            addr, MAX_ADDRESS
+        )),
+    }
+}
+
+fn parse_count(n: &str) -> Result<usize, String> {
+    match n.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("invalid count: {}", n)),
+    }
+}
+
+fn run(options: &Options) -> Result<i32, String> {
+    let code =
+        fs::read_to_string(&options.file).map_err(|err| format!("{}: {}", options.file, err))?;
+    let mut program = Intcode::from(parse_codes(&code)?);
+    for &(addr, n) in options.patches.iter() {
+        program.poke(addr, n);
+    }
+
+    if options.disasm {
+        for line in disassemble(&program.memory().to_vec()) {
+            println!("{}", line);
+        }
+        return Ok(HALTED);
+    }
+
+    program.with_input(options.input.clone());
+    let mut heatmap = options
+        .heatmap
+        .as_ref()
+        .map(|_| match options.heatmap_every {
+            Some(every) => Heatmap::new().every(every),
+            None => Heatmap::new(),
+        });
+    let result = execute(&mut program, options, heatmap.as_mut());
+
+    // The heatmap is written even when the program fails, since it shows what led up to it.
+    if let (Some(path), Some(heatmap)) = (&options.heatmap, &heatmap) {
+        write_heatmap(path, heatmap, options.heatmap_width.unwrap_or(64))?;
+    }
+
+    // Memory is shown wherever the program stopped, like day02's answer at 0 after it halts.
+    let code = result?;
+    for &addr in options.peeks.iter() {
+        println!("[{}] = {}", addr, program.peek(addr));
+    }
+    Ok(code)
+}
+
+fn execute(
+    program: &mut Intcode,
+    options: &Options,
+    mut heatmap: Option<&mut Heatmap>,
+) -> Result<i32, String> {
+    let mut stdin = io::stdin().lock();
+
+    loop {
+        if let Some(max_steps) = options.max_steps {
+            if program.steps() >= max_steps {
+                flush(program, options.ascii)?;
+                eprintln!("stopped after {} steps", max_steps);
+                return Ok(STEP_LIMIT);
+            }
+        }
+
+        let instruction = validate(program)?;
+        if options.trace && program.state() != State::Waiting {
+            eprintln!(
+                "{:>5}  {:<28}  rb={}",
+                program.ip(),
+                instruction.to_string(),
+                program.relative_base()
+            );
+        }
+
+        let signal = match heatmap.as_mut() {
+            Some(heatmap) => heatmap.step(program),
+            None => program.step(),
+        };
+        flush(program, options.ascii)?;
+        match signal {
+            None => {}
+            Some(Signal::Halt) => return Ok(HALTED),
+            Some(Signal::Waiting) if options.ascii => {
+                let mut line = String::new();
+                if stdin.read_line(&mut line).map_err(|err| err.to_string())? == 0 {
+                    return Ok(STARVED);
+                }
+                program.input.extend(line.chars().map(|c| c as i128));
+            }
+            Some(Signal::Waiting) => {
+                eprintln!("waiting for input at {}", program.ip());
+                return Ok(STARVED);
+            }
+        }
+    }
+}
+
+// Checks that the next instruction can run, instead of letting the machine panic.
+fn validate(program: &Intcode) -> Result<Instruction, String> {
+    let ip = program.ip();
+    let instruction = Instruction::at(program).ok_or(format!(
+        "invalid instruction {} at {}",
+        program.peek(ip),
+        ip
+    ))?;
+
+    let overflow = || format!("overflow at {}: {}", ip, instruction);
+    let mut values = vec![];
+    for &(mode, n) in instruction.params.iter() {
+        let addr = match mode {
+            ParameterMode::Position => n,
+            ParameterMode::Immediate => {
+                values.push(n);
+                continue;
+            }
+            ParameterMode::Relative => n
+                .checked_add(program.relative_base())
+                .ok_or_else(overflow)?,
+        };
+        let addr = usize::try_from(addr)
+            .map_err(|_| format!("negative address {} at {}: {}", addr, ip, instruction))?;
+        values.push(program.peek(addr));
+    }
+
+    // The machine itself would wrap or panic.
+    let result = match (instruction.op, values.as_slice()) {
+        (Operation::Add, [a, b, ..]) => a.checked_add(*b),
+        (Operation::Mul, [a, b, ..]) => a.checked_mul(*b),
+        (Operation::RelativeBase, [a]) => program.relative_base().checked_add(*a),
+        _ => Some(0),
+    };
+    result.ok_or_else(overflow)?;
+
+    Ok(instruction)
+}
+
+// Writes the final heatmap to `path`, and with --heatmap-every the frames next to it as
+// `out-0001.ppm`, `out-0002.ppm` and so on.
+fn write_heatmap(path: &str, heatmap: &Heatmap, width: usize) -> Result<(), String> {
+    let write = |path: &Path, image: &[u8]| {
+        fs::write(path, image).map_err(|err| format!("{}: {}", path.display(), err))
+    };
+    let path = Path::new(path);
+    write(path, &heatmap.counts().ppm(width))?;
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let frames = heatmap.frames(width);
+    if frames.len() > 1 {
+        for (i, frame) in frames.iter().enumerate() {
+            write(
+                &path.with_file_name(format!("{}-{:04}.ppm", stem, i + 1)),
+                frame,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Prints pending outputs, as text in ASCII mode.
+fn flush(program: &mut Intcode, ascii: bool) -> Result<(), String> {
+    let mut stdout = io::stdout().lock();
+    for n in program.output.drain(..) {
+        match n {
+            0..=127 if ascii => write!(stdout, "{}", n as u8 as char),
+            _ => writeln!(stdout, "{}", n),
+        }
+        .map_err(|err| format!("stdout: {}", err))?;
+    }
+    stdout.flush().map_err(|err| format!("stdout: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn parse() {
+        let options = parse_args(&args(
+            "run day02.txt --patch 1=12 --patch 2=2 --max-steps 100 --trace --peek 0",
+        ))
+        .unwrap();
+        assert_eq!(
+            Options {
+                file: "day02.txt".to_string(),
+                patches: vec![(1, 12), (2, 2)],
+                peeks: vec![0],
+                max_steps: Some(100),
+                trace: true,
+                ..Options::default()
+            },
+            options
+        );
+
+        let options = parse_args(&args("run day09.txt --input 1,-2 --ascii")).unwrap();
+        assert_eq!(vec![1, -2], options.input);
+        assert!(options.ascii);
+
+        assert!(parse_args(&args("day09.txt")).is_err());
+        assert!(parse_args(&args("run day09.txt --patch 1")).is_err());
+        assert!(parse_args(&args("run day09.txt --input")).is_err());
+        assert!(parse_args(&args("run day09.txt --verbose")).is_err());
+        assert!(parse_args(&args("run day09.txt --peek -1")).is_err());
+        assert!(parse_args(&args("run day09.txt --peek 9223372036854775807")).is_err());
+        assert!(parse_args(&args("run day09.txt --patch 9223372036854775807=1")).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+pub const PAGE_SIZE: usize = 256;
+
+type Page = [i128; PAGE_SIZE];
+
+static ZERO: i128 = 0;
+
+// Paged, copy-on-write memory. Cloning only copies the page table, so forks of a machine share
+// every page until one of them writes to it. Pages that were never written are not allocated,
+// and the table only holds the pages that were, so a write far past the image costs one page.
+#[derive(Clone, Default)]
+pub struct Memory {
+    pages: BTreeMap<usize, Arc<Page>>,
+    len: usize,
+}
+
+impl Memory {
+    // Number of cells in the image, including every cell written past its end.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, addr: usize) -> i128 {
+        self[addr]
+    }
+
+    pub fn set(&mut self, addr: usize, n: i128) {
+        *self.get_mut(addr) = n;
+    }
+
+    fn get_mut(&mut self, addr: usize) -> &mut i128 {
+        self.len = self.len.max(addr.saturating_add(1));
+        let page = self
+            .pages
+            .entry(addr / PAGE_SIZE)
+            .or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        &mut Arc::make_mut(page)[addr % PAGE_SIZE]
+    }
+
+    // Number of allocated pages that are shared with another fork.
+    pub fn shared_pages(&self) -> usize {
+        self.pages
+            .values()
+            .filter(|page| Arc::strong_count(page) > 1)
+            .count()
+    }
+
+    pub fn to_vec(&self) -> Vec<i128> {
+        (0..self.len).map(|addr| self.get(addr)).collect()
+    }
+}
+
+impl From<Vec<i128>> for Memory {
+    fn from(codes: Vec<i128>) -> Self {
+        let pages = codes
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = [0; PAGE_SIZE];
+                page[..chunk.len()].copy_from_slice(chunk);
+                Arc::new(page)
+            })
+            .enumerate()
+            .collect();
+        Memory {
+            pages,
+            len: codes.len(),
+        }
+    }
+}
+
+// Reads past the end of the image are zero, like `Intcode::exec`.
+impl Index<usize> for Memory {
+    type Output = i128;
+
+    fn index(&self, addr: usize) -> &i128 {
+        match self.pages.get(&(addr / PAGE_SIZE)) {
+            Some(page) => &page[addr % PAGE_SIZE],
+            None => &ZERO,
+        }
+    }
+}
+
+// Writes past the end of the image grow it.
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, addr: usize) -> &mut i128 {
+        self.get_mut(addr)
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Memory) -> bool {
+        // Only pages allocated on either side can differ, and a missing page is all zeros.
+        let indices = self.pages.keys().chain(other.pages.keys());
+        self.len == other.len
+            && indices.copied().all(|index| {
+                let start = index * PAGE_SIZE;
+                (start..=start + (PAGE_SIZE - 1)).all(|addr| self[addr] == other[addr])
+            })
+    }
+}
+
+impl PartialEq<Vec<i128>> for Memory {
+    fn eq(&self, other: &Vec<i128>) -> bool {
+        self.len == other.len()
+            && other
+                .iter()
+                .enumerate()
+                .all(|(addr, &n)| self.get(addr) == n)
+    }
+}
+
+impl PartialEq<Memory> for Vec<i128> {
+    fn eq(&self, other: &Memory) -> bool {
+        other == self
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.to_vec()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_on_write() {
+        let mut memory = Memory::from((0..1000).collect::<Vec<i128>>());
+        assert_eq!(1000, memory.len());
+        assert_eq!(999, memory[999]);
+        assert_eq!(0, memory[5000], "reads past the end are zero");
+
+        let mut fork = memory.clone();
+        assert_eq!(4, memory.shared_pages());
+
+        fork[300] = -1;
+        assert_eq!(3, memory.shared_pages(), "only the written page is copied");
+        assert_eq!(300, memory[300]);
+        assert_eq!(-1, fork[300]);
+
+        memory[5000] = 1;
+        assert_eq!(5001, memory.len());
+        assert_eq!(1000, fork.len());
+        assert_eq!(5, memory.pages.len(), "sparse");
+    }
+
+    #[test]
+    fn far_writes() {
+        // A write far past the image allocates one page, not a table reaching up to it.
+        let far = i64::MAX as usize;
+        let mut memory = Memory::from(vec![1, 2, 3]);
+        memory.set(far, 7);
+        assert_eq!(7, memory.get(far));
+        assert_eq!(0, memory.get(far - 1));
+        assert_eq!(far + 1, memory.len());
+        assert_eq!(2, memory.pages.len());
+
+        let mut other = Memory::from(vec![1, 2, 3]);
+        other.set(far, 7);
+        assert_eq!(memory, other);
+        other.set(far, 8);
+        assert_ne!(memory, other);
+
+        memory.set(usize::MAX, 1);
+        other.set(usize::MAX, 1);
+        assert_eq!(usize::MAX, memory.len());
+        assert_ne!(memory, other);
+    }
+
+    #[test]
+    fn compare() {
+        let memory = Memory::from(vec![1, 2, 3]);
+        assert_eq!(vec![1, 2, 3], memory);
+        assert_eq!(memory, vec![1, 2, 3]);
+        assert_ne!(memory, vec![1, 2, 3, 0]);
+        assert_eq!("[1, 2, 3]", format!("{:?}", memory));
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Operation {
     Add = 1,
     Mul = 2,
@@ -12,20 +12,50 @@ pub enum Operation {
     Halt = 99,
 }
 
+impl Operation {
+    pub fn parse(n: i128) -> Option<Self> {
+        match n {
+            1 => Some(Operation::Add),
+            2 => Some(Operation::Mul),
+            3 => Some(Operation::Input),
+            4 => Some(Operation::Output),
+            5 => Some(Operation::JumpIfTrue),
+            6 => Some(Operation::JumpIfFalse),
+            7 => Some(Operation::LessThan),
+            8 => Some(Operation::Equal),
+            9 => Some(Operation::RelativeBase),
+            99 => Some(Operation::Halt),
+            _ => None,
+        }
+    }
+
+    // Number of parameters that follow the opcode.
+    pub fn arity(&self) -> usize {
+        use Operation::*;
+        match self {
+            Add | Mul | LessThan | Equal => 3,
+            JumpIfTrue | JumpIfFalse => 2,
+            Input | Output | RelativeBase => 1,
+            Halt => 0,
+        }
+    }
+
+    // The parameter position (1-based) that is written to, if any.
+    pub fn writes(&self) -> Option<usize> {
+        use Operation::*;
+        match self {
+            Add | Mul | LessThan | Equal => Some(3),
+            Input => Some(1),
+            _ => None,
+        }
+    }
+}
+
 impl From<i128> for Operation {
     fn from(n: i128) -> Self {
-        match n {
-            1 => Operation::Add,
-            2 => Operation::Mul,
-            3 => Operation::Input,
-            4 => Operation::Output,
-            5 => Operation::JumpIfTrue,
-            6 => Operation::JumpIfFalse,
-            7 => Operation::LessThan,
-            8 => Operation::Equal,
-            9 => Operation::RelativeBase,
-            99 => Operation::Halt,
-            _ => unimplemented!("invalid operation: {}", n),
+        match Operation::parse(n) {
+            Some(op) => op,
+            None => unimplemented!("invalid operation: {}", n),
         }
     }
 }
@@ -0,0 +1,320 @@
+mod analysis;
+mod stores;
+mod value;
+
+use crate::instruction::Instruction;
+use crate::{Operation, ParameterMode};
+use analysis::Analysis;
+use std::collections::HashSet;
+use std::fmt;
+
+// Passes the analysis may take to settle. The puzzle inputs settle within 20.
+const PASSES: usize = 64;
+
+// Why the optimizer refused to rewrite a program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bailout {
+    // A jump target may be any value, so any cell may be executed.
+    IndirectJump(usize),
+    // Control may reach cells written through addresses with no upper bound, which may hold
+    // any number of instructions.
+    Unbounded(usize),
+    // The analysis was still finding new stores after this many passes.
+    Diverged(usize),
+}
+
+impl fmt::Display for Bailout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bailout::IndirectJump(ip) => write!(f, "indirect jump at {}", ip),
+            Bailout::Unbounded(ip) => write!(f, "unbounded writes run at {}", ip),
+            Bailout::Diverged(passes) => write!(f, "no fixed point after {} passes", passes),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Report {
+    // Addresses of arithmetic and comparison instructions folded into a constant store.
+    pub folded: Vec<usize>,
+    // Addresses of stores to cells that are never read or executed, replaced by a jump.
+    pub dead_stores: Vec<usize>,
+    // Addresses of jumps retargeted past jumps and no-ops.
+    pub threaded: Vec<usize>,
+    // Addresses of reachable instructions that may be overwritten at runtime, left as they are.
+    pub tainted: Vec<usize>,
+    pub bailout: Option<Bailout>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(bailout) = self.bailout {
+            return write!(f, "unchanged: {}", bailout);
+        }
+        write!(
+            f,
+            "folded: {}, dead stores: {}, threaded: {}, tainted: {}",
+            self.folded.len(),
+            self.dead_stores.len(),
+            self.threaded.len(),
+            self.tainted.len()
+        )
+    }
+}
+
+// Rewrites a program into a semantically equivalent one. Equivalence covers the input and output
+// behaviour only: stores to cells that are never read are removed, so the final memory may differ.
+//
+// The optimizer follows the relative base through calls and returns to find every cell an
+// instruction may read or write, and only rewrites instructions whose cells are never written
+// or read as data. Self-modifying code leaves just the instructions it may overwrite as they are.
+// A jump whose target may be anything could land in the middle of any instruction, so it leaves
+// the whole program unchanged, with the reason recorded in the report. So does an analysis that
+// has not settled after `PASSES` passes.
+pub fn optimize(codes: &[i128]) -> (Vec<i128>, Report) {
+    optimize_within(codes, PASSES)
+}
+
+fn optimize_within(codes: &[i128], passes: usize) -> (Vec<i128>, Report) {
+    let mut report = Report::default();
+    let mut analysis = Analysis::new(codes, passes);
+    report.tainted = analysis.tainted.clone();
+    if let Some(bailout) = analysis.bailout {
+        report.bailout = Some(bailout);
+        return (codes.to_vec(), report);
+    }
+
+    let mut out = codes.to_vec();
+    let addrs = analysis
+        .instructions
+        .keys()
+        .copied()
+        .collect::<Vec<usize>>();
+    let rewritable = addrs
+        .iter()
+        .copied()
+        .filter(|&ip| analysis.rewritable(ip))
+        .collect::<HashSet<usize>>();
+
+    let emit = |out: &mut Vec<i128>, ip: usize, instruction: &Instruction| {
+        for (i, n) in instruction.encode().into_iter().enumerate() {
+            out[ip + i] = n;
+        }
+    };
+
+    // Constant folding.
+    for &ip in addrs.iter().filter(|ip| rewritable.contains(ip)) {
+        let instruction = &analysis.instructions[&ip];
+        let (a, b, dst) = match instruction.params[..] {
+            [(ParameterMode::Immediate, a), (ParameterMode::Immediate, b), dst] => (a, b, dst),
+            _ => continue,
+        };
+        let n = match instruction.op {
+            Operation::Add if b == 0 => continue,
+            Operation::Add => a.checked_add(b),
+            Operation::Mul => a.checked_mul(b),
+            Operation::LessThan => Some((a < b) as i128),
+            Operation::Equal => Some((a == b) as i128),
+            _ => continue,
+        };
+        // Constants too large to fold are left for the machine to overflow on.
+        let Some(n) = n else {
+            continue;
+        };
+        let folded = Instruction {
+            op: Operation::Add,
+            params: vec![
+                (ParameterMode::Immediate, n),
+                (ParameterMode::Immediate, 0),
+                dst,
+            ],
+        };
+        emit(&mut out, ip, &folded);
+        analysis.instructions.insert(ip, folded);
+        report.folded.push(ip);
+    }
+
+    // Dead-store elimination. The store becomes an unconditional jump to the next instruction.
+    for &ip in addrs.iter().filter(|ip| rewritable.contains(ip)) {
+        let instruction = &analysis.instructions[&ip];
+        let dst = match (instruction.op, &instruction.params[..]) {
+            (
+                Operation::Add | Operation::Mul | Operation::LessThan | Operation::Equal,
+                [_, _, (ParameterMode::Position | ParameterMode::Immediate, dst)],
+            ) if *dst >= 0 => *dst as usize,
+            _ => continue,
+        };
+        if analysis.read(dst) || analysis.executed(dst) {
+            continue;
+        }
+        let jump = Instruction {
+            op: Operation::JumpIfTrue,
+            params: vec![
+                (ParameterMode::Immediate, 1),
+                (ParameterMode::Immediate, (ip + instruction.size()) as i128),
+            ],
+        };
+        emit(&mut out, ip, &jump);
+        analysis.instructions.insert(ip, jump);
+        report.dead_stores.push(ip);
+    }
+
+    // Jump threading. Follow chains of unconditional jumps and never-taken jumps to their end.
+    let follow = |target: usize| -> usize {
+        let mut seen = HashSet::new();
+        let mut target = target;
+        while seen.insert(target) {
+            let instruction = match analysis.instructions.get(&target) {
+                Some(instruction) => instruction,
+                None => break,
+            };
+            let (cond, dst) = match (instruction.op, &instruction.params[..]) {
+                (
+                    Operation::JumpIfTrue | Operation::JumpIfFalse,
+                    [(ParameterMode::Immediate, cond), (ParameterMode::Immediate, dst)],
+                ) => (*cond, *dst),
+                _ => break,
+            };
+            let taken = (cond != 0) == (instruction.op == Operation::JumpIfTrue);
+            target = match taken {
+                true if dst >= 0 => dst as usize,
+                true => break,
+                false => target + instruction.size(),
+            };
+        }
+        target
+    };
+
+    for &ip in addrs.iter().filter(|ip| rewritable.contains(ip)) {
+        let instruction = &analysis.instructions[&ip];
+        let target = match (instruction.op, &instruction.params[..]) {
+            (Operation::JumpIfTrue, [(ParameterMode::Immediate, 0), _]) => continue,
+            (Operation::JumpIfFalse, [(ParameterMode::Immediate, cond), _]) if *cond != 0 => {
+                continue
+            }
+            (
+                Operation::JumpIfTrue | Operation::JumpIfFalse,
+                [_, (ParameterMode::Immediate, target)],
+            ) if *target >= 0 => *target as usize,
+            _ => continue,
+        };
+        let threaded = follow(target);
+        if threaded != target {
+            out[ip + 2] = threaded as i128;
+            report.threaded.push(ip);
+        }
+    }
+
+    (out, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Intcode;
+
+    fn run(codes: &[i128], input: Vec<i128>) -> Vec<i128> {
+        let mut program = Intcode::from(codes.to_vec());
+        program.with_input(input);
+        program.run_until_halt()
+    }
+
+    fn assert_equivalent(code: &str, inputs: &[i128]) -> Report {
+        let codes = Intcode::parse(code);
+        let (optimized, report) = optimize(&codes);
+        for &input in inputs {
+            assert_eq!(
+                run(&codes, vec![input]),
+                run(&optimized, vec![input]),
+                "input {}",
+                input
+            );
+        }
+        report
+    }
+
+    #[test]
+    fn fold_and_eliminate() {
+        // Stores 2 * 3 to 17 and outputs it, then stores 4 + 5 to 18 which is never read.
+        let codes = Intcode::parse("1102,2,3,17,4,17,1101,4,5,18,1105,1,14,0,99,0,0,0,0");
+        let (optimized, report) = optimize(&codes);
+        assert_eq!(vec![0, 6], report.folded);
+        assert_eq!(vec![6], report.dead_stores);
+        assert_eq!(
+            vec![6],
+            report.threaded,
+            "dead store jumps past the jump at 10"
+        );
+        assert_eq!(None, report.bailout);
+        assert_eq!(vec![1101, 6, 0, 17], optimized[0..4]);
+        assert_eq!(vec![1105, 1, 14], optimized[6..9]);
+        assert_eq!(run(&codes, vec![]), run(&optimized, vec![]));
+    }
+
+    #[test]
+    fn thread_jumps() {
+        // 0: jump to 3, 3: jump to 6, 6: never-taken jump, 9: output 99 and halt.
+        let codes = Intcode::parse("1105,1,3,1105,1,6,1106,1,0,104,99,99");
+        let (optimized, report) = optimize(&codes);
+        assert_eq!(vec![0, 3], report.threaded);
+        assert_eq!(9, optimized[2]);
+        assert_eq!(9, optimized[5]);
+        assert_eq!(vec![99], run(&optimized, vec![]));
+    }
+
+    #[test]
+    fn taint() {
+        // Relative addressing only touches the cells at the base.
+        let report = assert_equivalent("109,10,203,0,204,0,99", &[7]);
+        assert_eq!(None, report.bailout);
+        assert!(report.tainted.is_empty());
+
+        // The add at 2 is rewritten by the input before it runs, so only the mul at 8 is folded.
+        let code = "3,3,1101,0,0,5,4,5,1102,2,3,15,4,15,99,0";
+        let report = assert_equivalent(code, &[7]);
+        assert_eq!(vec![2], report.tainted);
+        assert_eq!(vec![8], report.folded);
+
+        // The jump target at 11 is overwritten by the input.
+        let report = assert_equivalent("3,11,105,1,11,104,1,99,104,2,99,5", &[5, 8]);
+        assert_eq!(Some(Bailout::IndirectJump(2)), report.bailout);
+    }
+
+    #[test]
+    fn overflow() {
+        let code = format!("1102,{},2,9,1101,1,2,9,99,0", i128::MAX);
+        let (_, report) = optimize(&Intcode::parse(&code));
+        assert_eq!(vec![4], report.folded);
+    }
+
+    #[test]
+    fn pass_limit() {
+        // The day09 self-test takes 11 passes to settle.
+        let codes = Intcode::parse(include_str!("../../day09/src/input.txt").trim());
+        let (optimized, report) = optimize_within(&codes, 10);
+        assert_eq!(Some(Bailout::Diverged(10)), report.bailout);
+        assert_eq!(codes, optimized);
+
+        let (_, report) = optimize_within(&codes, 11);
+        assert_eq!(None, report.bailout);
+    }
+
+    #[test]
+    fn diagnostics() {
+        // The day05 diagnostic patches its own opcode at 6 with the input, and nothing else.
+        let day05 = include_str!("../../day05/src/input.txt").trim();
+        let report = assert_equivalent(day05, &[1, 5]);
+        assert_eq!(None, report.bailout);
+        assert_eq!(vec![6], report.tainted);
+        assert!(!report.folded.is_empty(), "{}", report);
+        assert!(!report.threaded.is_empty(), "{}", report);
+
+        // The day09 self-test reads and writes relative to the base, and calls a recursive
+        // function for part 2.
+        let day09 = include_str!("../../day09/src/input.txt").trim();
+        let report = assert_equivalent(day09, &[1, 2]);
+        assert_eq!(None, report.bailout);
+        assert!(report.tainted.is_empty());
+        assert!(!report.folded.is_empty(), "{}", report);
+    }
+}
@@ -0,0 +1,717 @@
+use super::stores::Stores;
+use super::value::{Span, Value};
+use super::Bailout;
+use crate::instruction::Instruction;
+use crate::{Operation, ParameterMode};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Times the relative base on entry to a function may move before it is assumed unbounded in the
+// direction it moves.
+const WIDEN: usize = 4;
+
+// The program starts as a function at 0 whose cells hold their initial values.
+const MAIN: usize = 0;
+
+// What is known at one point of a function, relative to the relative base on entry to it.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    // How far the relative base moved since entry, if it is the same on every path.
+    delta: Option<i128>,
+    // Cells written since entry through the relative base, by offset from the base on entry.
+    slots: BTreeMap<i128, Value>,
+    // Writes since entry through other addresses, which may have hit any slot in their span.
+    clobbers: BTreeMap<(usize, usize), (Span, Value)>,
+    // Offset from which every slot not written since may hold anything, once the frames of
+    // recursive calls are too deep to follow one by one.
+    above: Option<i128>,
+}
+
+impl State {
+    fn entry() -> Self {
+        State {
+            delta: Some(0),
+            slots: BTreeMap::new(),
+            clobbers: BTreeMap::new(),
+            above: None,
+        }
+    }
+
+    fn clobber(&mut self, site: (usize, usize), span: Span, value: &Value) {
+        let joined = match self.clobbers.get(&site) {
+            Some((old, written)) => (old.join(span), written.join(value)),
+            None => (span, value.clone()),
+        };
+        self.clobbers.insert(site, joined);
+    }
+}
+
+// Code entered by a call, which the puzzle programs make by storing the return address at
+// [rb+0] and jumping. Its returns are resolved against the slots of each caller.
+struct Function {
+    // Relative base on entry, over every call.
+    base: Span,
+    moves: usize,
+    // The return address and state at each return, by the address of the returning jump.
+    exits: BTreeMap<usize, (Value, State)>,
+    returns: usize,
+}
+
+impl Function {
+    fn new(base: Span) -> Self {
+        Function {
+            base,
+            moves: 0,
+            exits: BTreeMap::new(),
+            returns: 0,
+        }
+    }
+
+    // Widens the base for a call with `base`, returning whether it moved.
+    fn enter(&mut self, base: Span) -> bool {
+        let mut joined = self.base.join(base);
+        if joined == self.base {
+            return false;
+        }
+        self.moves += 1;
+        if self.moves > WIDEN {
+            if joined.lo < self.base.lo {
+                joined.lo = i128::MIN;
+            }
+            if joined.hi > self.base.hi {
+                joined.hi = i128::MAX;
+            }
+        }
+        // Returns seen from the old base may have placed absolute addresses in slots.
+        self.base = joined;
+        self.exits.clear();
+        self.returns = 0;
+        true
+    }
+}
+
+// The operation and parameter modes of an opcode, as `Instruction::decode` reads them.
+fn decode(code: i128) -> Option<(Operation, Vec<ParameterMode>)> {
+    if code < 0 {
+        return None;
+    }
+    let op = Operation::parse(code % 100)?;
+    let mut modes = code / 100;
+    let mut params = Vec::with_capacity(op.arity());
+    for _ in 0..op.arity() {
+        params.push(ParameterMode::parse(modes % 10)?);
+        modes /= 10;
+    }
+    Some((op, params))
+}
+
+// Every operation with every parameter mode, for a cell that may hold any opcode.
+fn every() -> Vec<(Operation, Vec<ParameterMode>)> {
+    (1..=9)
+        .chain([99])
+        .filter_map(Operation::parse)
+        .flat_map(|op| {
+            (0..10_i128.pow(op.arity() as u32))
+                .filter_map(move |modes| decode(modes * 100 + op as i128))
+        })
+        .collect()
+}
+
+// Everything the optimizer knows about the cells of a program without running it. The program
+// is followed from 0 with abstract values, through calls and returns, until nothing it may store
+// changes. Each pass starts over with what the last one found stored. An analysis still changing
+// after the given number of passes bails out, since what it found so far may be missing stores.
+pub(crate) struct Analysis<'a> {
+    stores: Stores<'a>,
+    functions: BTreeMap<usize, Function>,
+    // Whether a function moved or returned differently during the pass.
+    changed: bool,
+    queue: Vec<(usize, usize)>,
+    states: BTreeMap<(usize, usize), State>,
+    // Addresses reached by control flow, with the most cells an instruction there may take.
+    starts: BTreeMap<usize, usize>,
+    // Cells read as data, by instruction and parameter.
+    reads: BTreeMap<(usize, usize), Span>,
+    // Successors of each instruction, including the calls and returns it makes.
+    edges: BTreeMap<usize, BTreeSet<usize>>,
+    pub(crate) bailout: Option<Bailout>,
+    // Reachable instructions that are never overwritten.
+    pub(crate) instructions: BTreeMap<usize, Instruction>,
+    pub(crate) tainted: Vec<usize>,
+}
+
+impl<'a> Analysis<'a> {
+    pub(crate) fn new(codes: &'a [i128], passes: usize) -> Self {
+        let mut analysis = Analysis {
+            stores: Stores::new(codes),
+            functions: BTreeMap::from([(MAIN, Function::new(Span::at(0)))]),
+            changed: false,
+            queue: vec![],
+            states: BTreeMap::new(),
+            starts: BTreeMap::new(),
+            reads: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            bailout: None,
+            instructions: BTreeMap::new(),
+            tainted: vec![],
+        };
+        let mut changed = 0;
+        while analysis.pass() {
+            changed += 1;
+            if changed == passes {
+                analysis.bailout = Some(Bailout::Diverged(passes));
+                return analysis;
+            }
+        }
+
+        for (&ip, &size) in analysis.starts.iter() {
+            if analysis.stores.written(Span::cells(ip, size), ip) {
+                analysis.tainted.push(ip);
+            } else if let Some(instruction) = Instruction::decode(codes, ip) {
+                analysis.instructions.insert(ip, instruction);
+            }
+        }
+        analysis
+    }
+
+    // Follows every function once, returning whether anything it found stored or any edge
+    // changed.
+    fn pass(&mut self) -> bool {
+        self.stores.reach = self.reach();
+        let edges = std::mem::take(&mut self.edges);
+        self.stores.changed = false;
+        self.changed = false;
+        self.states.clear();
+        self.starts.clear();
+        self.reads.clear();
+        self.bailout = None;
+
+        for &entry in self.functions.keys() {
+            self.states.insert((entry, entry), State::entry());
+            self.queue.push((entry, entry));
+        }
+        while let Some((function, ip)) = self.queue.pop() {
+            let state = self.states[&(function, ip)].clone();
+            for (next, state) in self.step(function, ip, state) {
+                self.edge(ip, next);
+                self.merge(function, next, state);
+            }
+        }
+        self.stores.changed || self.changed || self.edges != edges
+    }
+
+    // Addresses reachable from each instruction that writes.
+    fn reach(&self) -> BTreeMap<usize, BTreeSet<usize>> {
+        let writers = self
+            .stores
+            .cells
+            .values()
+            .flat_map(|writers| writers.keys());
+        let writers = writers
+            .chain(self.stores.spans.keys().map(|(writer, _)| writer))
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        writers
+            .into_iter()
+            .map(|writer| {
+                let mut reach = BTreeSet::new();
+                let mut queue = vec![writer];
+                while let Some(ip) = queue.pop() {
+                    for &next in self.edges.get(&ip).into_iter().flatten() {
+                        if reach.insert(next) {
+                            queue.push(next);
+                        }
+                    }
+                }
+                (writer, reach)
+            })
+            .collect()
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        self.edges.entry(from).or_default().insert(to);
+    }
+
+    fn merge(&mut self, function: usize, ip: usize, state: State) {
+        let merged = match self.states.get(&(function, ip)) {
+            Some(old) => {
+                let joined = self.join(function, old, &state);
+                if &joined == old {
+                    return;
+                }
+                joined
+            }
+            None => state,
+        };
+        self.states.insert((function, ip), merged);
+        self.queue.push((function, ip));
+    }
+
+    fn join(&self, function: usize, a: &State, b: &State) -> State {
+        let keys = a.slots.keys().chain(b.slots.keys()).copied();
+        let slots = keys
+            .collect::<BTreeSet<i128>>()
+            .into_iter()
+            .map(|k| {
+                (
+                    k,
+                    self.slot(function, a, k).join(&self.slot(function, b, k)),
+                )
+            })
+            .collect();
+        let mut joined = State {
+            delta: if a.delta == b.delta { a.delta } else { None },
+            slots,
+            clobbers: a.clobbers.clone(),
+            above: match (a.above, b.above) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        };
+        for (&site, (span, value)) in b.clobbers.iter() {
+            joined.clobber(site, *span, value);
+        }
+        joined
+    }
+
+    // Value of the cell at `k` from the relative base on entry to the function.
+    fn slot(&self, function: usize, state: &State, k: i128) -> Value {
+        if let Some(value) = state.slots.get(&k) {
+            return value.clone();
+        }
+        if state.above.is_some_and(|above| k >= above) {
+            return Value::Any;
+        }
+        let span = self.functions[&function].base.shift(k);
+        let mut value = match function {
+            MAIN => self.stores.initial(k),
+            _ => Value::Entry(k),
+        };
+        for (clobber, written) in state.clobbers.values() {
+            if clobber.overlaps(span) {
+                value = value.join(written);
+            }
+        }
+        value
+    }
+
+    // Offset from the base on entry of the cell a parameter addresses, if it is exactly known.
+    // Absolute addresses have one when every call enters the function with the same base.
+    fn offset(
+        &self,
+        function: usize,
+        state: &State,
+        mode: ParameterMode,
+        n: &Value,
+    ) -> Option<i128> {
+        let n = n.single()?;
+        let base = self.functions[&function].base;
+        match mode {
+            ParameterMode::Relative => state.delta?.checked_add(n),
+            _ if base.lo == base.hi => n.checked_sub(base.lo),
+            _ => None,
+        }
+    }
+
+    // Cells a parameter may address, if it is not exactly known.
+    fn span(&self, function: usize, state: &State, mode: ParameterMode, n: &Value) -> Span {
+        match (mode, state.delta) {
+            (ParameterMode::Relative, Some(delta)) => {
+                self.functions[&function].base.shift(delta).add(n.span())
+            }
+            (ParameterMode::Relative, None) => Span::ALL,
+            _ => n.span(),
+        }
+    }
+
+    fn clobber(
+        &self,
+        function: usize,
+        state: &mut State,
+        site: (usize, usize),
+        span: Span,
+        value: &Value,
+    ) {
+        let base = self.functions[&function].base;
+        for (&k, slot) in state.slots.iter_mut() {
+            if base.shift(k).overlaps(span) {
+                *slot = slot.join(value);
+            }
+        }
+        state.clobber(site, span, value);
+    }
+
+    fn observe(&mut self, site: (usize, usize), span: Span) {
+        let joined = self.reads.get(&site).map_or(span, |read| read.join(span));
+        self.reads.insert(site, joined);
+    }
+
+    fn load(
+        &mut self,
+        function: usize,
+        state: &State,
+        site: (usize, usize),
+        param: &(ParameterMode, Value),
+    ) -> Value {
+        let (mode, n) = param;
+        if *mode == ParameterMode::Immediate {
+            return n.clone();
+        }
+        if let Some(k) = self.offset(function, state, *mode, n) {
+            self.observe(site, self.functions[&function].base.shift(k));
+            return self.slot(function, state, k);
+        }
+        let span = self.span(function, state, *mode, n);
+        self.observe(site, span);
+        match (mode, n) {
+            (ParameterMode::Position, Value::Known(addrs)) => addrs
+                .iter()
+                .map(|&addr| self.stores.read(addr, site.0))
+                .reduce(|a, b| a.join(&b))
+                .unwrap_or(Value::Any),
+            _ => self.stores.read_span(span, site.0),
+        }
+    }
+
+    fn store(
+        &mut self,
+        function: usize,
+        state: &mut State,
+        site: (usize, usize),
+        param: &(ParameterMode, Value),
+        value: Value,
+    ) {
+        let (mode, n) = param;
+        if let Some(k) = self.offset(function, state, *mode, n) {
+            let span = self.functions[&function].base.shift(k);
+            self.stores.write(site, span, &value);
+            state.slots.insert(k, value);
+            return;
+        }
+        let span = self.span(function, state, *mode, n);
+        match (mode, n) {
+            // Immediate mode writes to the address like position mode does.
+            (ParameterMode::Position | ParameterMode::Immediate, Value::Known(addrs)) => {
+                for &addr in addrs {
+                    self.stores.write(site, Span::at(addr), &value);
+                }
+            }
+            _ => self.stores.write(site, span, &value),
+        }
+        self.clobber(function, state, site, span, &value);
+    }
+
+    // Runs every instruction the cell at `ip` may hold, returning where each may go next.
+    fn step(&mut self, function: usize, ip: usize, state: State) -> Vec<(usize, State)> {
+        if self.stores.unbounded(ip) {
+            self.bailout.get_or_insert(Bailout::Unbounded(ip));
+            return vec![];
+        }
+        let variants = match self.stores.read(ip as i128, ip) {
+            Value::Known(codes) => codes.iter().filter_map(|&code| decode(code)).collect(),
+            _ => every(),
+        };
+        // A cell that holds no valid opcode would crash the machine, so paths end there.
+        let size = variants
+            .iter()
+            .map(|(_, modes)| 1 + modes.len())
+            .max()
+            .unwrap_or(1);
+        let extent = self.starts.entry(ip).or_insert(size);
+        *extent = size.max(*extent);
+
+        let mut successors = vec![];
+        for (op, modes) in variants {
+            let params = modes
+                .into_iter()
+                .enumerate()
+                .map(|(pos, mode)| (mode, self.stores.read((ip + 1 + pos) as i128, ip)))
+                .collect::<Vec<(ParameterMode, Value)>>();
+            successors.extend(self.execute(function, ip, op, &params, state.clone()));
+        }
+        successors
+    }
+
+    fn execute(
+        &mut self,
+        function: usize,
+        ip: usize,
+        op: Operation,
+        params: &[(ParameterMode, Value)],
+        mut state: State,
+    ) -> Vec<(usize, State)> {
+        let next = ip + 1 + params.len();
+        match op {
+            Operation::Add | Operation::Mul | Operation::LessThan | Operation::Equal => {
+                let a = self.load(function, &state, (ip, 0), &params[0]);
+                let b = self.load(function, &state, (ip, 1), &params[1]);
+                let value = match op {
+                    Operation::Add => a.combine(&b, i128::checked_add),
+                    Operation::Mul => a.combine(&b, i128::checked_mul),
+                    Operation::LessThan => a.combine(&b, |a, b| Some((a < b) as i128)),
+                    _ => a.combine(&b, |a, b| Some((a == b) as i128)),
+                };
+                // Comparisons store 0 or 1 whatever they compare.
+                let value = match (op, value) {
+                    (Operation::LessThan | Operation::Equal, Value::Any) => {
+                        Value::known(BTreeSet::from([0, 1]))
+                    }
+                    (_, value) => value,
+                };
+                self.store(function, &mut state, (ip, 2), &params[2], value);
+                vec![(next, state)]
+            }
+            Operation::Input => {
+                self.store(function, &mut state, (ip, 0), &params[0], Value::Any);
+                vec![(next, state)]
+            }
+            Operation::Output => {
+                self.load(function, &state, (ip, 0), &params[0]);
+                vec![(next, state)]
+            }
+            Operation::JumpIfTrue | Operation::JumpIfFalse => {
+                let cond = self.load(function, &state, (ip, 0), &params[0]);
+                let target = self.load(function, &state, (ip, 1), &params[1]);
+                let jumps = |n: &i128| (*n != 0) == (op == Operation::JumpIfTrue);
+                let (taken, skipped) = match &cond {
+                    Value::Known(values) => (values.iter().any(jumps), !values.iter().all(jumps)),
+                    _ => (true, true),
+                };
+                let mut successors = vec![];
+                if skipped {
+                    successors.push((next, state.clone()));
+                }
+                if taken {
+                    successors.extend(self.jump(function, ip, next, target, state));
+                }
+                successors
+            }
+            Operation::RelativeBase => {
+                let n = self.load(function, &state, (ip, 0), &params[0]);
+                state.delta = match (state.delta, n.single()) {
+                    (Some(delta), Some(n)) => delta.checked_add(n),
+                    _ => None,
+                };
+                vec![(next, state)]
+            }
+            Operation::Halt => vec![],
+        }
+    }
+
+    fn jump(
+        &mut self,
+        function: usize,
+        ip: usize,
+        next: usize,
+        target: Value,
+        state: State,
+    ) -> Vec<(usize, State)> {
+        let targets = match target {
+            Value::Known(targets) => targets,
+            Value::Entry(k) => {
+                self.exit(function, ip, Value::Entry(k), state);
+                return vec![];
+            }
+            Value::Any => {
+                self.bailout.get_or_insert(Bailout::IndirectJump(ip));
+                return vec![];
+            }
+        };
+
+        let mut successors = vec![];
+        // Jumps below 0 crash the machine.
+        for target in targets
+            .into_iter()
+            .filter_map(|target| usize::try_from(target).ok())
+        {
+            let delta = state
+                .delta
+                .filter(|&delta| self.slot(function, &state, delta) == Value::of(next as i128));
+            match delta {
+                Some(delta) if target != MAIN => {
+                    successors.extend(self.call(function, ip, target, delta, &state))
+                }
+                _ => successors.push((target, state.clone())),
+            }
+        }
+        successors
+    }
+
+    // Follows a call through the returns of the function it enters.
+    fn call(
+        &mut self,
+        function: usize,
+        ip: usize,
+        target: usize,
+        delta: i128,
+        state: &State,
+    ) -> Vec<(usize, State)> {
+        let base = self.functions[&function].base.shift(delta);
+        match self.functions.get_mut(&target) {
+            Some(callee) => self.changed |= callee.enter(base),
+            None => {
+                self.functions.insert(target, Function::new(base));
+                self.merge(target, target, State::entry());
+                self.changed = true;
+            }
+        }
+
+        // Whatever the callee writes may be seen after any of its calls.
+        self.edge(ip, target);
+        let mut successors = vec![];
+        let exits = self.functions[&target].exits.clone();
+        for (exit, (to, after)) in exits {
+            self.edge(exit, ip);
+            let (to, after) = self.returned(function, delta, state, &to, &after);
+            match to {
+                Value::Known(targets) => successors.extend(
+                    targets
+                        .into_iter()
+                        .filter_map(|target| usize::try_from(target).ok())
+                        .map(|target| (target, after.clone())),
+                ),
+                // The callee returns straight to our caller.
+                Value::Entry(k) => self.exit(function, ip, Value::Entry(k), after),
+                Value::Any => {
+                    self.bailout.get_or_insert(Bailout::IndirectJump(exit));
+                }
+            }
+        }
+        successors
+    }
+
+    // The caller's state after a return from a call made with the base `delta` past its own,
+    // and where the return goes. The callee's values on entry are the caller's slots at the call.
+    fn returned(
+        &self,
+        function: usize,
+        delta: i128,
+        state: &State,
+        to: &Value,
+        exit: &State,
+    ) -> (Value, State) {
+        let resolve = |value: &Value| match value {
+            Value::Entry(k) => self.slot(function, state, delta.saturating_add(*k)),
+            value => value.clone(),
+        };
+        let mut after = state.clone();
+        after.delta = exit.delta.and_then(|moved| delta.checked_add(moved));
+        if let Some(above) = exit.above {
+            let above = delta.saturating_add(above);
+            after.slots.split_off(&above);
+            after.above = Some(after.above.map_or(above, |old| old.min(above)));
+        }
+        for (&k, value) in exit.slots.iter() {
+            after.slots.insert(delta.saturating_add(k), resolve(value));
+        }
+        for (&site, (span, value)) in exit.clobbers.iter() {
+            self.clobber(function, &mut after, site, *span, &resolve(value));
+        }
+        (resolve(to), after)
+    }
+
+    fn exit(&mut self, function: usize, ip: usize, to: Value, state: State) {
+        let exit = &self.functions[&function];
+        let mut joined = match exit.exits.get(&ip) {
+            Some((old_to, old)) => (old_to.join(&to), self.join(function, old, &state)),
+            None => (to, state),
+        };
+        // A recursive function adds a frame's worth of slots each time round, so after a few
+        // rounds everything from the lowest new slot up is given up on.
+        if exit.returns > WIDEN {
+            let old = exit.exits.get(&ip).map(|(_, old)| &old.slots);
+            let new = joined
+                .1
+                .slots
+                .keys()
+                .find(|k| old.is_none_or(|old| !old.contains_key(k)));
+            if let Some(&k) = new {
+                let k = if k > 0 { k } else { i128::MIN };
+                let above = joined.1.above.map_or(k, |above| above.min(k));
+                joined.1.slots.split_off(&above);
+                joined.1.above = Some(above);
+            }
+        }
+        if exit.exits.get(&ip) == Some(&joined) {
+            return;
+        }
+        let exit = self.functions.get_mut(&function).unwrap();
+        exit.exits.insert(ip, joined);
+        exit.returns += 1;
+        self.changed = true;
+    }
+
+    // An instruction may only be rewritten if none of its cells are observed as data and no
+    // other instruction starts inside it.
+    pub(crate) fn rewritable(&self, ip: usize) -> bool {
+        let size = self.instructions[&ip].size();
+        let cells = Span::cells(ip, size);
+        self.reads.values().all(|read| !read.overlaps(cells))
+            && self.starts.range(ip + 1..ip + size).next().is_none()
+            && self
+                .starts
+                .range(..ip)
+                .all(|(&start, &extent)| start + extent <= ip)
+    }
+
+    pub(crate) fn read(&self, addr: usize) -> bool {
+        let cell = Span::at(addr as i128);
+        self.reads.values().any(|read| read.overlaps(cell))
+    }
+
+    pub(crate) fn executed(&self, addr: usize) -> bool {
+        self.starts
+            .range(..=addr)
+            .any(|(&start, &extent)| addr < start + extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Intcode;
+
+    #[test]
+    fn opcodes() {
+        use ParameterMode::*;
+        assert_eq!(
+            Some((Operation::Mul, vec![Position, Immediate, Position])),
+            decode(1002)
+        );
+        assert_eq!(None, decode(-1));
+        assert_eq!(None, decode(42));
+        assert_eq!(None, decode(301));
+
+        // 3 modes for each parameter of each operation.
+        assert_eq!(4 * 27 + 2 * 9 + 3 * 3 + 1, every().len());
+    }
+
+    #[test]
+    fn call_and_return() {
+        // 0: move the base to 100, 2: store the return address 9 at [rb+0], 6: call 12,
+        // 9: output 1 and halt, 12: return through [rb+0].
+        let codes = Intcode::parse("109,100,21101,9,0,0,1105,1,12,104,1,99,2105,1,0");
+        let analysis = Analysis::new(&codes, 8);
+        assert_eq!(None, analysis.bailout);
+        assert_eq!(Span::at(100), analysis.functions[&12].base);
+        assert_eq!(
+            vec![0, 2, 6, 9, 11, 12],
+            analysis.starts.keys().copied().collect::<Vec<usize>>()
+        );
+        assert!(analysis.edges[&12].contains(&6), "returns to the call");
+        assert!(analysis.read(100), "the return address is read");
+        assert!(!analysis.read(9));
+        assert!(analysis.tainted.is_empty());
+
+        // Stopping before the returns are followed gives up on the program.
+        let analysis = Analysis::new(&codes, 1);
+        assert_eq!(Some(Bailout::Diverged(1)), analysis.bailout);
+    }
+
+    #[test]
+    fn indirect_jump() {
+        // The jump target at 7 is read from the input.
+        let codes = Intcode::parse("3,7,105,1,7,99,0,0");
+        let analysis = Analysis::new(&codes, 8);
+        assert_eq!(Some(Bailout::IndirectJump(2)), analysis.bailout);
+    }
+}
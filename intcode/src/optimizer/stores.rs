@@ -0,0 +1,173 @@
+use super::value::{Span, Value, CONSTANTS};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Every value the program may store, and which instructions may run after each store.
+pub(crate) struct Stores<'a> {
+    codes: &'a [i128],
+    // Cells written through an exact address, by the address and the writing instruction.
+    pub(crate) cells: BTreeMap<usize, BTreeMap<usize, Value>>,
+    // Spans written through addresses that are not exactly known, by instruction and parameter.
+    pub(crate) spans: BTreeMap<(usize, usize), (Span, Value)>,
+    // Addresses reachable from each writing instruction, over the edges of the last pass. A
+    // store is only seen by the instructions that may run after it.
+    pub(crate) reach: BTreeMap<usize, BTreeSet<usize>>,
+    pub(crate) changed: bool,
+}
+
+impl<'a> Stores<'a> {
+    pub(crate) fn new(codes: &'a [i128]) -> Self {
+        Stores {
+            codes,
+            cells: BTreeMap::new(),
+            spans: BTreeMap::new(),
+            reach: BTreeMap::new(),
+            changed: false,
+        }
+    }
+
+    pub(crate) fn initial(&self, addr: i128) -> Value {
+        let n = usize::try_from(addr)
+            .ok()
+            .and_then(|addr| self.codes.get(addr));
+        Value::of(n.copied().unwrap_or(0))
+    }
+
+    fn before(&self, writer: usize, at: usize) -> bool {
+        self.reach
+            .get(&writer)
+            .is_some_and(|reach| reach.contains(&at))
+    }
+
+    // Values the cell at `addr` may hold when the instruction at `at` reads it.
+    pub(crate) fn read(&self, addr: i128, at: usize) -> Value {
+        let mut value = self.initial(addr);
+        let cell = usize::try_from(addr)
+            .ok()
+            .and_then(|addr| self.cells.get(&addr));
+        for (&writer, written) in cell.into_iter().flatten() {
+            if self.before(writer, at) {
+                value = value.join(written);
+            }
+        }
+        for (&(writer, _), (span, written)) in self.spans.iter() {
+            if span.overlaps(Span::at(addr)) && self.before(writer, at) {
+                value = value.join(written);
+            }
+        }
+        value
+    }
+
+    pub(crate) fn read_span(&self, span: Span, at: usize) -> Value {
+        match span.hi.checked_sub(span.lo) {
+            Some(n) if n < CONSTANTS as i128 => (span.lo..=span.hi)
+                .map(|addr| self.read(addr, at))
+                .reduce(|a, b| a.join(&b))
+                .unwrap_or(Value::Any),
+            _ => Value::Any,
+        }
+    }
+
+    // Whether a cell in `span` may have been written when the instruction at `at` runs.
+    pub(crate) fn written(&self, span: Span, at: usize) -> bool {
+        let cells = self.cells.range(span.lo.max(0) as usize..);
+        cells
+            .take_while(|(&addr, _)| addr as i128 <= span.hi)
+            .any(|(_, writers)| writers.keys().any(|&writer| self.before(writer, at)))
+            || self.spans.iter().any(|(&(writer, _), (written, _))| {
+                span.overlaps(*written) && self.before(writer, at)
+            })
+    }
+
+    // Whether the instruction at `at` may run cells written through addresses with no upper
+    // bound, which could hold any number of instructions.
+    pub(crate) fn unbounded(&self, at: usize) -> bool {
+        self.spans.iter().any(|(&(writer, _), (span, _))| {
+            span.hi == i128::MAX && span.overlaps(Span::at(at as i128)) && self.before(writer, at)
+        })
+    }
+
+    pub(crate) fn write(&mut self, site: (usize, usize), span: Span, value: &Value) {
+        // Values on entry to a function mean nothing outside of it.
+        let value = match value {
+            Value::Entry(_) => Value::Any,
+            value => value.clone(),
+        };
+        if span.lo == span.hi {
+            // The machine cannot write below address 0.
+            let Ok(addr) = usize::try_from(span.lo) else {
+                return;
+            };
+            let writers = self.cells.entry(addr).or_default();
+            let joined = match writers.get(&site.0) {
+                Some(old) => old.join(&value),
+                None => value,
+            };
+            if writers.get(&site.0) != Some(&joined) {
+                writers.insert(site.0, joined);
+                self.changed = true;
+            }
+        } else {
+            let joined = match self.spans.get(&site) {
+                Some((old, written)) => (old.join(span), written.join(&value)),
+                None => (span, value),
+            };
+            if self.spans.get(&site) != Some(&joined) {
+                self.spans.insert(site, joined);
+                self.changed = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reach() {
+        let codes = [1, 2, 3];
+        let mut stores = Stores::new(&codes);
+        stores.write((10, 2), Span::at(1), &Value::of(7));
+        stores.reach = BTreeMap::from([(10, BTreeSet::from([20]))]);
+
+        // Only instructions that may run after the write see it.
+        assert_eq!(Value::known(BTreeSet::from([2, 7])), stores.read(1, 20));
+        assert_eq!(Value::of(2), stores.read(1, 30));
+        assert!(stores.written(Span::cells(0, 2), 20));
+        assert!(!stores.written(Span::cells(0, 2), 30));
+
+        // Cells past the end of the program start at 0.
+        assert_eq!(Value::of(0), stores.read(5, 20));
+    }
+
+    #[test]
+    fn spans() {
+        let codes = [0; 4];
+        let mut stores = Stores::new(&codes);
+        stores.reach = BTreeMap::from([(10, BTreeSet::from([20]))]);
+        stores.write((10, 0), Span { lo: 2, hi: 3 }, &Value::Entry(0));
+        assert!(stores.changed);
+
+        // Values on entry to a function are unknown once stored.
+        assert_eq!(Value::Any, stores.read(3, 20));
+        assert_eq!(Value::of(0), stores.read(1, 20));
+        assert_eq!(Value::Any, stores.read_span(Span { lo: 1, hi: 2 }, 20));
+        assert!(!stores.unbounded(20));
+
+        // Writing what is already known changes nothing.
+        stores.changed = false;
+        stores.write((10, 0), Span { lo: 2, hi: 3 }, &Value::Any);
+        assert!(!stores.changed);
+
+        stores.write(
+            (10, 0),
+            Span {
+                lo: 2,
+                hi: i128::MAX,
+            },
+            &Value::Any,
+        );
+        assert!(stores.changed);
+        assert!(stores.unbounded(20));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeSet;
+
+// Constants a cell may hold before the analysis assumes it may hold anything.
+pub(crate) const CONSTANTS: usize = 16;
+
+// Inclusive range of addresses. An end at i128::MIN or i128::MAX leaves that side unbounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Span {
+    pub(crate) lo: i128,
+    pub(crate) hi: i128,
+}
+
+impl Span {
+    pub(crate) const ALL: Span = Span {
+        lo: i128::MIN,
+        hi: i128::MAX,
+    };
+
+    pub(crate) fn at(addr: i128) -> Self {
+        Span { lo: addr, hi: addr }
+    }
+
+    pub(crate) fn cells(ip: usize, size: usize) -> Self {
+        Span {
+            lo: ip as i128,
+            hi: (ip + size) as i128 - 1,
+        }
+    }
+
+    // Every sum of an address in `self` and one in `other`.
+    pub(crate) fn add(self, other: Span) -> Self {
+        let end = |a: i128, b: i128, bound: i128| match a == bound || b == bound {
+            true => bound,
+            false => a.saturating_add(b),
+        };
+        Span {
+            lo: end(self.lo, other.lo, i128::MIN),
+            hi: end(self.hi, other.hi, i128::MAX),
+        }
+    }
+
+    pub(crate) fn shift(self, n: i128) -> Self {
+        self.add(Span::at(n))
+    }
+
+    pub(crate) fn join(self, other: Span) -> Self {
+        Span {
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+
+    pub(crate) fn overlaps(self, other: Span) -> bool {
+        self.lo <= other.hi && other.lo <= self.hi
+    }
+}
+
+// What the analysis knows about the contents of a cell.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    // One of a few constants.
+    Known(BTreeSet<i128>),
+    // Whatever was at this offset from the relative base when the function was entered.
+    Entry(i128),
+    Any,
+}
+
+impl Value {
+    pub(crate) fn of(n: i128) -> Self {
+        Value::Known(BTreeSet::from([n]))
+    }
+
+    pub(crate) fn known(values: BTreeSet<i128>) -> Self {
+        match values.len() > CONSTANTS {
+            true => Value::Any,
+            false => Value::Known(values),
+        }
+    }
+
+    pub(crate) fn single(&self) -> Option<i128> {
+        match self {
+            Value::Known(values) if values.len() == 1 => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    // Smallest span holding every constant, or every address if they are not known.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Value::Known(values) => Span {
+                lo: values.first().copied().unwrap_or(0),
+                hi: values.last().copied().unwrap_or(0),
+            },
+            _ => Span::ALL,
+        }
+    }
+
+    pub(crate) fn join(&self, other: &Value) -> Value {
+        match (self, other) {
+            (Value::Known(a), Value::Known(b)) => Value::known(a.union(b).copied().collect()),
+            (a, b) if a == b => a.clone(),
+            _ => Value::Any,
+        }
+    }
+
+    // Applies `f` to every pair of constants. A pair that overflows may be anything.
+    pub(crate) fn combine<F>(&self, other: &Value, f: F) -> Value
+    where
+        F: Fn(i128, i128) -> Option<i128>,
+    {
+        let (Value::Known(a), Value::Known(b)) = (self, other) else {
+            return Value::Any;
+        };
+        let mut values = BTreeSet::new();
+        for &x in a {
+            for &y in b {
+                match f(x, y) {
+                    Some(n) => values.insert(n),
+                    None => return Value::Any,
+                };
+            }
+        }
+        Value::known(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans() {
+        let span = Span { lo: 2, hi: 5 };
+        assert_eq!(Span { lo: 12, hi: 15 }, span.shift(10));
+        assert_eq!(Span { lo: 2, hi: 9 }, span.join(Span::at(9)));
+        assert!(span.overlaps(Span::at(5)));
+        assert!(!span.overlaps(Span::cells(6, 4)));
+
+        // Unbounded ends stay unbounded, and bounded ones saturate instead of wrapping.
+        let above = Span {
+            lo: 0,
+            hi: i128::MAX,
+        };
+        assert_eq!(
+            Span {
+                lo: -3,
+                hi: i128::MAX
+            },
+            above.shift(-3)
+        );
+        let near = Span::at(i128::MAX - 1);
+        assert_eq!(Span::at(i128::MAX), near.shift(5));
+    }
+
+    #[test]
+    fn values() {
+        let a = Value::known(BTreeSet::from([1, 2]));
+        let b = Value::of(10);
+        assert_eq!(
+            Value::known(BTreeSet::from([11, 12])),
+            a.combine(&b, i128::checked_add)
+        );
+        assert_eq!(Span { lo: 1, hi: 10 }, a.join(&b).span());
+        assert_eq!(Some(10), b.single());
+        assert_eq!(None, a.single());
+
+        // Any pair that overflows, or any unknown side, may give anything.
+        let max = Value::of(i128::MAX);
+        assert_eq!(Value::Any, a.combine(&max, i128::checked_add));
+        assert_eq!(Value::Any, a.combine(&Value::Entry(0), i128::checked_add));
+        assert_eq!(Value::Entry(0), Value::Entry(0).join(&Value::Entry(0)));
+        assert_eq!(Value::Any, Value::Entry(0).join(&Value::Entry(1)));
+        assert_eq!(Span::ALL, Value::Any.span());
+
+        // Past a few constants a cell is assumed to hold anything.
+        let many = (0..=CONSTANTS as i128).collect::<BTreeSet<i128>>();
+        assert_eq!(Value::Any, Value::known(many));
+    }
+}
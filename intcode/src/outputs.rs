@@ -0,0 +1,124 @@
+use crate::frame::{Frame, Frames};
+use crate::{Intcode, Signal};
+
+// Iterator that drives the machine one instruction at a time and yields each output as soon as
+// it is produced. Yielded values are taken out of `Intcode::output`.
+//
+// The iterator ends when the machine halts, or when it waits for input and there is no feed (or
+// the feed returns None). The machine can be resumed afterwards.
+pub struct Outputs<'a> {
+    program: &'a mut Intcode,
+    feed: Option<Box<dyn FnMut() -> Option<i128> + 'a>>,
+}
+
+impl<'a> Outputs<'a> {
+    pub(crate) fn new(program: &'a mut Intcode) -> Self {
+        Outputs {
+            program,
+            feed: None,
+        }
+    }
+
+    // Calls `feed` for the next input whenever the machine blocks on an empty input.
+    pub fn feed<F>(mut self, feed: F) -> Self
+    where
+        F: FnMut() -> Option<i128> + 'a,
+    {
+        self.feed = Some(Box::new(feed));
+        self
+    }
+
+    // Groups the outputs into frames of `n` values, e.g. (x, y, tile_id) for day13. The last
+    // frame is shorter if the machine stops in the middle of one.
+    pub fn chunks(self, n: usize) -> Chunks<'a> {
+        assert!(n > 0, "chunk size must be non-zero");
+        Chunks { outputs: self, n }
+    }
+
+    // Decodes the outputs as typed messages. Unlike `chunks`, a trailing partial frame is an
+    // error.
+    pub fn frames<F: Frame>(self) -> Frames<'a, F> {
+        Frames::new(self)
+    }
+}
+
+impl<'a> Iterator for Outputs<'a> {
+    type Item = i128;
+
+    fn next(&mut self) -> Option<i128> {
+        loop {
+            if !self.program.output.is_empty() {
+                return Some(self.program.output.remove(0));
+            }
+
+            match self.program.step() {
+                None => continue,
+                Some(Signal::Halt) => return None,
+                Some(Signal::Waiting) => {
+                    let input = self.feed.as_mut().and_then(|feed| feed())?;
+                    self.program.input.push(input);
+                }
+            }
+        }
+    }
+}
+
+pub struct Chunks<'a> {
+    outputs: Outputs<'a>,
+    n: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Vec<i128>;
+
+    fn next(&mut self) -> Option<Vec<i128>> {
+        let chunk = self.outputs.by_ref().take(self.n).collect::<Vec<i128>>();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Intcode;
+
+    #[test]
+    fn outputs() {
+        let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut program = Intcode::new(code, vec![]);
+        assert_eq!(
+            Intcode::parse(code),
+            program.outputs().collect::<Vec<i128>>()
+        );
+        assert!(program.output.is_empty(), "outputs are consumed");
+    }
+
+    #[test]
+    fn chunks() {
+        let mut program = Intcode::new("104,1,104,2,104,3,104,4,104,5,99", vec![]);
+        let chunks = program.outputs().chunks(2).collect::<Vec<Vec<i128>>>();
+        assert_eq!(vec![vec![1, 2], vec![3, 4], vec![5]], chunks);
+    }
+
+    #[test]
+    fn feed() {
+        // Echoes every input until it reads 0.
+        let code = "3,9,4,9,1005,9,0,99,0,0";
+        let mut inputs = vec![3, 2, 1, 0].into_iter();
+        let mut program = Intcode::new(code, vec![]);
+        let outputs = program
+            .outputs()
+            .feed(|| inputs.next())
+            .collect::<Vec<i128>>();
+        assert_eq!(vec![3, 2, 1, 0], outputs);
+
+        // Without a feed, the iterator stops when the machine blocks.
+        let mut program = Intcode::new(code, vec![5]);
+        assert_eq!(vec![5], program.outputs().collect::<Vec<i128>>());
+        program.set_input(0);
+        assert_eq!(vec![0], program.outputs().collect::<Vec<i128>>());
+    }
+}
@@ -1,17 +1,26 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ParameterMode {
     Position = 0,
     Immediate = 1,
     Relative = 2,
 }
 
+impl ParameterMode {
+    pub fn parse(n: i128) -> Option<Self> {
+        match n {
+            0 => Some(ParameterMode::Position),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
+        }
+    }
+}
+
 impl From<i128> for ParameterMode {
     fn from(n: i128) -> Self {
-        match n {
-            0 => ParameterMode::Position,
-            1 => ParameterMode::Immediate,
-            2 => ParameterMode::Relative,
-            _ => unimplemented!(),
+        match ParameterMode::parse(n) {
+            Some(mode) => mode,
+            None => unimplemented!(),
         }
     }
 }
@@ -0,0 +1,356 @@
+// Runs machines as futures on a single-threaded executor, so several machines can be connected
+// with channels and driven by plain async code. Enabled with the `async` feature.
+use crate::{Intcode, Signal};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Default)]
+struct Queue {
+    values: VecDeque<i128>,
+    waiting: Vec<Waker>,
+    misses: usize,
+}
+
+// An unbounded queue of values. Clones share the same queue, so one clone can be handed to the
+// writer and another to the reader.
+#[derive(Clone, Default)]
+pub struct Channel(Rc<RefCell<Queue>>);
+
+impl Channel {
+    pub fn new() -> Self {
+        Channel::default()
+    }
+
+    // Queues the value and wakes the tasks waiting on the channel.
+    pub fn send(&self, value: i128) {
+        let mut queue = self.0.borrow_mut();
+        queue.values.push_back(value);
+        queue.misses = 0;
+        for waker in queue.waiting.drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<i128> {
+        let mut queue = self.0.borrow_mut();
+        let value = queue.values.pop_front();
+        if value.is_none() {
+            queue.misses += 1;
+        }
+        value
+    }
+
+    // Waits until a value is available.
+    pub fn recv(&self) -> Recv<'_> {
+        Recv { channel: self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Number of reads that found the channel empty since the last send. A machine whose input has
+    // missed twice in a row has gone idle.
+    pub fn misses(&self) -> usize {
+        self.0.borrow().misses
+    }
+
+    fn wait(&self, waker: &Waker) {
+        self.0.borrow_mut().waiting.push(waker.clone());
+    }
+}
+
+pub struct Recv<'a> {
+    channel: &'a Channel,
+}
+
+impl Future for Recv<'_> {
+    type Output = i128;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<i128> {
+        match self.channel.try_recv() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                self.channel.wait(cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// Runs a machine until it halts and resolves to the halted machine. Input instructions read from
+// the input channel, and outputs are sent to the output channel as soon as they are produced.
+pub struct AsyncIntcode {
+    program: Intcode,
+    input: Channel,
+    output: Channel,
+    default: Option<i128>,
+    yielded: bool,
+}
+
+impl AsyncIntcode {
+    pub fn new(program: Intcode, input: Channel, output: Channel) -> Self {
+        AsyncIntcode {
+            program,
+            input,
+            output,
+            default: None,
+            yielded: false,
+        }
+    }
+
+    // Reads the value instead of waiting when the input channel is empty, and then yields to the
+    // other tasks before the next read. day23's computers read -1 when no packet has arrived.
+    pub fn nonblocking(mut self, value: i128) -> Self {
+        self.default = Some(value);
+        self
+    }
+}
+
+impl Future for AsyncIntcode {
+    type Output = Intcode;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Intcode> {
+        let machine = &mut *self;
+        loop {
+            let signal = machine.program.step();
+            for value in machine.program.output.drain(..) {
+                machine.output.send(value);
+            }
+
+            match signal {
+                None => {}
+                Some(Signal::Halt) => return Poll::Ready(machine.program.fork()),
+                Some(Signal::Waiting) => match (machine.input.try_recv(), machine.default) {
+                    (Some(value), _) => {
+                        machine.program.input.push(value);
+                        machine.yielded = false;
+                    }
+                    (None, Some(value)) if !machine.yielded => {
+                        machine.program.input.push(value);
+                        machine.yielded = true;
+                    }
+                    (None, Some(_)) => {
+                        machine.yielded = false;
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    (None, None) => {
+                        machine.input.wait(cx.waker());
+                        return Poll::Pending;
+                    }
+                },
+            }
+        }
+    }
+}
+
+// Lets the other tasks run before continuing.
+pub fn yield_now() -> YieldNow {
+    YieldNow(false)
+}
+
+pub struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+// Wakes a task by queueing its id. The main future of `block_on` has no task id.
+struct TaskWaker {
+    id: Option<usize>,
+    ready: Arc<Mutex<VecDeque<Option<usize>>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+// A single-threaded executor that polls tasks in the order they are woken.
+#[derive(Default)]
+pub struct Executor {
+    tasks: Vec<Option<Task>>,
+    ready: Arc<Mutex<VecDeque<Option<usize>>>>,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Executor::default()
+    }
+
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.ready.lock().unwrap().push_back(Some(self.tasks.len()));
+        self.tasks.push(Some(Box::pin(future)));
+    }
+
+    // Runs the spawned tasks until the future completes. Returns None if every task is waiting on
+    // something that can no longer happen.
+    pub fn block_on<F: Future>(&mut self, future: F) -> Option<F::Output> {
+        let mut future = Box::pin(future);
+        self.ready.lock().unwrap().push_back(None);
+
+        loop {
+            let next = self.ready.lock().unwrap().pop_front();
+            let id = next?;
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: self.ready.clone(),
+            }));
+            let mut cx = Context::from_waker(&waker);
+
+            match id {
+                None => {
+                    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                        return Some(output);
+                    }
+                }
+                Some(id) => {
+                    // Tasks are woken once per value sent, so finished ones can still be queued.
+                    if let Some(task) = self.tasks[id].as_mut() {
+                        if task.as_mut().poll(&mut cx).is_ready() {
+                            self.tasks[id] = None;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Runs the spawned tasks until they finish or block.
+    pub fn run(&mut self) {
+        self.block_on(std::future::pending::<()>());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The day07 amplifiers as five tasks connected in a ring.
+    fn feedback_loop(code: &str, phases: &[i128]) -> i128 {
+        let mut executor = Executor::new();
+        let channels = phases
+            .iter()
+            .map(|&phase| {
+                let channel = Channel::new();
+                channel.send(phase);
+                channel
+            })
+            .collect::<Vec<Channel>>();
+        channels[0].send(0);
+
+        let n = phases.len();
+        for i in 0..n {
+            let amplifier = AsyncIntcode::new(
+                Intcode::new(code, vec![]),
+                channels[i].clone(),
+                channels[(i + 1) % n].clone(),
+            );
+            executor.spawn(async move {
+                amplifier.await;
+            });
+        }
+        executor.run();
+
+        // The last amplifier's final signal is left unread in the first channel.
+        channels[0].try_recv().unwrap()
+    }
+
+    #[test]
+    fn day07_feedback_loop() {
+        let code =
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        assert_eq!(139629729, feedback_loop(code, &[9, 8, 7, 6, 5]));
+
+        let code = "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10";
+        assert_eq!(18216, feedback_loop(code, &[9, 7, 8, 5, 6]));
+    }
+
+    #[test]
+    fn block_on_waits_for_outputs() {
+        let mut executor = Executor::new();
+        let (input, output) = (Channel::new(), Channel::new());
+        let doubler = AsyncIntcode::new(
+            Intcode::new("3,9,1002,9,2,9,4,9,1105,1,0", vec![]),
+            input.clone(),
+            output.clone(),
+        );
+        executor.spawn(async move {
+            doubler.await;
+        });
+
+        let sum = executor.block_on(async {
+            let mut sum = 0;
+            for n in 1..=3 {
+                input.send(n);
+                sum += output.recv().await;
+            }
+            sum
+        });
+        assert_eq!(Some(12), sum);
+
+        // Nothing is left to send the doubler input.
+        assert_eq!(None, executor.block_on(output.recv()));
+    }
+
+    #[test]
+    fn nonblocking_yields() {
+        let mut executor = Executor::new();
+        let (input, output) = (Channel::new(), Channel::new());
+        let echo = AsyncIntcode::new(
+            Intcode::new("3,7,4,7,1105,1,0,0", vec![]),
+            input.clone(),
+            output.clone(),
+        )
+        .nonblocking(-1);
+        executor.spawn(async move {
+            echo.await;
+        });
+
+        // The echo keeps reading -1 until the value arrives.
+        let outputs = executor
+            .block_on(async {
+                yield_now().await;
+                input.send(5);
+                let mut outputs = vec![output.recv().await];
+                while outputs.last() != Some(&5) {
+                    outputs.push(output.recv().await);
+                }
+                outputs
+            })
+            .unwrap();
+        assert!(outputs.len() > 1);
+        assert!(outputs[..outputs.len() - 1].iter().all(|&n| n == -1));
+
+        executor.block_on(async {
+            while input.misses() < 2 {
+                yield_now().await;
+            }
+        });
+    }
+}
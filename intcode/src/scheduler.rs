@@ -0,0 +1,339 @@
+use crate::{Intcode, Signal};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Policy {
+    // Every machine executes one instruction per turn.
+    RoundRobin,
+    // Every machine runs until it waits for input or halts.
+    RunUntilBlocked,
+    // Every machine executes up to the given number of instructions per turn.
+    TimeSlice(usize),
+}
+
+// Every machine that has not halted is waiting on an empty channel, or only reading its idle
+// value.
+#[derive(Debug, PartialEq)]
+pub struct Deadlock {
+    pub blocked: Vec<String>,
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "deadlock: {} waiting on input", self.blocked.join(", "))
+    }
+}
+
+impl Error for Deadlock {}
+
+struct Machine {
+    name: String,
+    program: Intcode,
+    input: String,
+    output: String,
+    halted: bool,
+    // Read instead of waiting when the input channel is empty.
+    idle: Option<i128>,
+    // Idle values read since the machine last read from its channel or wrote anything.
+    misses: usize,
+}
+
+// Takes a frame of values written to a channel and gives the channel and values to send on.
+type Route = Box<dyn FnMut(&[i128]) -> (String, Vec<i128>)>;
+
+struct Router {
+    frame: usize,
+    route: Route,
+}
+
+// Runs a set of machines cooperatively. Each machine reads from one named channel and writes to
+// another, so day07's amplifiers are five machines connected in a ring.
+pub struct Scheduler {
+    policy: Policy,
+    machines: Vec<Machine>,
+    channels: HashMap<String, VecDeque<i128>>,
+    routers: HashMap<String, Router>,
+}
+
+impl Scheduler {
+    pub fn new(policy: Policy) -> Self {
+        Scheduler {
+            policy,
+            machines: vec![],
+            channels: HashMap::new(),
+            routers: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, program: Intcode, input: &str, output: &str) {
+        self.machines.push(Machine {
+            name: name.to_string(),
+            program,
+            input: input.to_string(),
+            output: output.to_string(),
+            halted: false,
+            idle: None,
+            misses: 0,
+        });
+    }
+
+    // Makes the machine read `value` whenever its input channel is empty, rather than wait. It
+    // counts as blocked once it reads it twice in a row without writing anything.
+    pub fn idle(&mut self, name: &str, value: i128) {
+        for machine in self
+            .machines
+            .iter_mut()
+            .filter(|machine| machine.name == name)
+        {
+            machine.idle = Some(value);
+        }
+    }
+
+    // Sends each `frame` values written to `channel` on to wherever `route` says, with whatever
+    // values it gives, so a machine can address its writes. day23's computers write packets of
+    // three values, the first of which is the address of the computer to send the rest to.
+    pub fn route<F>(&mut self, channel: &str, frame: usize, route: F)
+    where
+        F: FnMut(&[i128]) -> (String, Vec<i128>) + 'static,
+    {
+        let router = Router {
+            frame: frame.max(1),
+            route: Box::new(route),
+        };
+        self.routers.insert(channel.to_string(), router);
+    }
+
+    pub fn send(&mut self, channel: &str, value: i128) {
+        self.channels
+            .entry(channel.to_string())
+            .or_default()
+            .push_back(value);
+    }
+
+    // Values written to the channel that have not been read yet.
+    pub fn channel(&self, name: &str) -> Vec<i128> {
+        self.channels
+            .get(name)
+            .map(|queue| queue.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Removes and returns the values written to the channel that have not been read yet.
+    pub fn take(&mut self, name: &str) -> Vec<i128> {
+        self.channels
+            .get_mut(name)
+            .map(|queue| queue.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn program(&self, name: &str) -> Option<&Intcode> {
+        self.machines
+            .iter()
+            .find(|machine| machine.name == name)
+            .map(|machine| &machine.program)
+    }
+
+    // Runs until every machine halts.
+    pub fn run(&mut self) -> Result<(), Deadlock> {
+        let budget = match self.policy {
+            Policy::RoundRobin => 1,
+            Policy::RunUntilBlocked => usize::MAX,
+            Policy::TimeSlice(n) => n.max(1),
+        };
+
+        loop {
+            let mut progressed = false;
+
+            for machine in self.machines.iter_mut().filter(|machine| !machine.halted) {
+                if let Some(queue) = self.channels.get_mut(&machine.input) {
+                    if !queue.is_empty() {
+                        machine.misses = 0;
+                    }
+                    machine.program.input.extend(queue.drain(..));
+                }
+
+                // A machine with an idle value reads it at most once per turn, so one that polls
+                // its input still gives the others a turn.
+                let mut fed = false;
+                let mut steps = 0;
+                while steps < budget {
+                    match machine.program.step() {
+                        None => {
+                            steps += 1;
+                            if machine.misses < 2 {
+                                progressed = true;
+                            }
+                        }
+                        Some(Signal::Halt) => {
+                            machine.halted = true;
+                            progressed = true;
+                            break;
+                        }
+                        Some(Signal::Waiting) => match machine.idle {
+                            Some(value) if !fed => {
+                                machine.program.input.push(value);
+                                machine.misses += 1;
+                                fed = true;
+                            }
+                            _ => break,
+                        },
+                    }
+                }
+
+                if !machine.program.output.is_empty() {
+                    machine.misses = 0;
+                    progressed = true;
+                }
+                let output = machine.program.output.drain(..);
+                self.channels
+                    .entry(machine.output.clone())
+                    .or_default()
+                    .extend(output);
+                Scheduler::forward(&mut self.channels, &mut self.routers, &machine.output);
+            }
+
+            if self.machines.iter().all(|machine| machine.halted) {
+                return Ok(());
+            }
+
+            // No machine could execute a single instruction other than to read its idle value,
+            // so none of them wrote anything that another machine could read.
+            if !progressed {
+                let blocked = self
+                    .machines
+                    .iter()
+                    .filter(|machine| !machine.halted)
+                    .map(|machine| machine.name.clone())
+                    .collect();
+                return Err(Deadlock { blocked });
+            }
+        }
+    }
+
+    // Passes every whole frame written to `channel` through its router, if it has one.
+    fn forward(
+        channels: &mut HashMap<String, VecDeque<i128>>,
+        routers: &mut HashMap<String, Router>,
+        channel: &str,
+    ) {
+        let Some(router) = routers.get_mut(channel) else {
+            return;
+        };
+        let queue = channels.entry(channel.to_string()).or_default();
+        let frames = queue.drain(..queue.len() / router.frame * router.frame);
+        let frames = frames.collect::<Vec<i128>>();
+        for frame in frames.chunks(router.frame) {
+            let (destination, values) = (router.route)(frame);
+            channels.entry(destination).or_default().extend(values);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adds one to every input until it reads 0.
+    const INCREMENT: &str = "3,15,1005,15,6,99,1001,15,1,15,4,15,1105,1,0,0";
+
+    #[test]
+    fn pipeline() {
+        for policy in [
+            Policy::RoundRobin,
+            Policy::RunUntilBlocked,
+            Policy::TimeSlice(3),
+        ] {
+            let mut scheduler = Scheduler::new(policy);
+            scheduler.add("a", Intcode::new(INCREMENT, vec![]), "in", "ab");
+            scheduler.add("b", Intcode::new(INCREMENT, vec![]), "ab", "out");
+            for n in [1, 2, 3] {
+                scheduler.send("in", n);
+            }
+            scheduler.send("in", 0);
+
+            assert_eq!(
+                Err(Deadlock {
+                    blocked: vec!["b".to_string()]
+                }),
+                scheduler.run(),
+                "{:?}: b never reads 0",
+                policy
+            );
+            assert!(scheduler.program("a").is_some());
+            assert_eq!(vec![3, 4, 5], scheduler.channel("out"), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn deadlock() {
+        let mut scheduler = Scheduler::new(Policy::RunUntilBlocked);
+        scheduler.add("a", Intcode::new(INCREMENT, vec![]), "ba", "ab");
+        scheduler.add("b", Intcode::new(INCREMENT, vec![]), "ab", "ba");
+        let deadlock = scheduler.run().unwrap_err();
+        assert_eq!(vec!["a", "b"], deadlock.blocked);
+        assert_eq!("deadlock: a, b waiting on input", deadlock.to_string());
+    }
+
+    #[test]
+    fn network() {
+        // day23's computers read -1 when they have no packet, and address every packet they
+        // write. The network is idle when the scheduler finds them all blocked.
+        let input = include_str!("../../day23/src/input.txt").trim();
+        let mut scheduler = Scheduler::new(Policy::RunUntilBlocked);
+        for address in 0..50 {
+            let (name, output) = (address.to_string(), format!("out{}", address));
+            scheduler.add(&name, Intcode::new(input, vec![]), &name, &output);
+            scheduler.idle(&name, -1);
+            scheduler.route(&output, 3, |packet| {
+                (packet[0].to_string(), packet[1..].to_vec())
+            });
+            scheduler.send(&name, address);
+        }
+
+        let mut first = None;
+        let mut delivered = None;
+        loop {
+            assert_eq!(50, scheduler.run().unwrap_err().blocked.len());
+            let nat = scheduler.take("255");
+            first = first.or(nat.get(1).copied());
+            let &[x, y] = &nat[nat.len() - 2..] else {
+                unreachable!()
+            };
+            if delivered == Some(y) {
+                break;
+            }
+            scheduler.send("0", x);
+            scheduler.send("0", y);
+            delivered = Some(y);
+        }
+        assert_eq!(Some(23954), first);
+        assert_eq!(Some(17265), delivered);
+    }
+
+    #[test]
+    fn route() {
+        // Sends each pair written to "out" to the channel named by its first value.
+        let mut scheduler = Scheduler::new(Policy::RoundRobin);
+        scheduler.add(
+            "a",
+            Intcode::new("104,1,104,5,104,2,104,6,104,1,99", vec![]),
+            "in",
+            "out",
+        );
+        scheduler.route("out", 2, |frame| (frame[0].to_string(), vec![frame[1]]));
+        assert_eq!(Ok(()), scheduler.run());
+        assert_eq!(vec![5], scheduler.channel("1"));
+        assert_eq!(vec![6], scheduler.channel("2"));
+        assert_eq!(vec![1], scheduler.channel("out"), "half a frame stays");
+    }
+
+    #[test]
+    fn halt() {
+        let mut scheduler = Scheduler::new(Policy::RoundRobin);
+        scheduler.add("a", Intcode::new("104,7,99", vec![]), "in", "out");
+        assert_eq!(Ok(()), scheduler.run());
+        assert_eq!(vec![7], scheduler.channel("out"));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{Intcode, Signal};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Input(i128),
+    Output(i128),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    // Milliseconds since the recording started.
+    pub millis: u128,
+    pub event: Event,
+}
+
+// Records every value the machine consumes or produces, in the order it happens.
+pub struct Recorder {
+    start: Instant,
+    entries: Vec<Entry>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder::new()
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            start: Instant::now(),
+            entries: vec![],
+        }
+    }
+
+    // Same as `Intcode::exec`, but records the exchange. Inputs are recorded when the machine
+    // reads them, not when they are queued.
+    pub fn exec(&mut self, program: &mut Intcode) -> Signal {
+        loop {
+            let pending = program.input.first().copied();
+            let (inputs, outputs) = (program.input.len(), program.output.len());
+            let signal = program.step();
+
+            if let (true, Some(n)) = (program.input.len() < inputs, pending) {
+                self.push(Event::Input(n));
+            }
+            if program.output.len() > outputs {
+                self.push(Event::Output(program.output[outputs]));
+            }
+            if let Some(signal) = signal {
+                return signal;
+            }
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        let millis = self.start.elapsed().as_millis();
+        self.entries.push(Entry { millis, event });
+    }
+
+    pub fn transcript(&self) -> Transcript {
+        Transcript {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+// The first point where a replay differs from the transcript. None means the event is missing.
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub expected: Option<Event>,
+    pub actual: Option<Event>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "diverged at event {}: expected {:?}, got {:?}",
+            self.index, self.expected, self.actual
+        )
+    }
+}
+
+impl Error for Divergence {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    pub entries: Vec<Entry>,
+}
+
+impl Transcript {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    // Runs the program with the recorded inputs, and checks that it produces the recorded
+    // outputs. The program must be set up the same way as the recorded one, including any inputs
+    // queued before the session started.
+    pub fn replay(&self, program: &mut Intcode) -> Result<(), Divergence> {
+        let mut recorder = Recorder::new();
+        let mut checked = 0;
+
+        loop {
+            let signal = recorder.exec(program);
+            for (index, entry) in recorder.entries.iter().enumerate().skip(checked) {
+                let expected = self.entries.get(index).map(|entry| entry.event);
+                if expected != Some(entry.event) {
+                    return Err(Divergence {
+                        index,
+                        expected,
+                        actual: Some(entry.event),
+                    });
+                }
+            }
+            checked = recorder.entries.len();
+
+            if signal == Signal::Halt {
+                break;
+            }
+
+            // Feed the next input the recording consumed, if any.
+            let consumed = recorder
+                .entries
+                .iter()
+                .filter(|entry| matches!(entry.event, Event::Input(_)))
+                .count();
+            let next = self
+                .entries
+                .iter()
+                .filter_map(|entry| match entry.event {
+                    Event::Input(n) => Some(n),
+                    _ => None,
+                })
+                .nth(consumed);
+            match next {
+                Some(n) => program.input.push(n),
+                None => break,
+            }
+        }
+
+        match self.entries.get(checked) {
+            Some(entry) => Err(Divergence {
+                index: checked,
+                expected: Some(entry.event),
+                actual: None,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// One event per line, e.g. `1520 in 10` or `1523 out 110`.
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.entries.iter() {
+            match entry.event {
+                Event::Input(n) => writeln!(f, "{} in {}", entry.millis, n)?,
+                Event::Output(n) => writeln!(f, "{} out {}", entry.millis, n)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Transcript {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = vec![];
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let parts = line.split_whitespace().collect::<Vec<&str>>();
+            let (millis, kind, n) = match parts[..] {
+                [millis, kind, n] => (millis.parse::<u128>()?, kind, n.parse::<i128>()?),
+                _ => return Err(format!("invalid entry: {}", line).into()),
+            };
+            let event = match kind {
+                "in" => Event::Input(n),
+                "out" => Event::Output(n),
+                _ => return Err(format!("invalid event: {}", kind).into()),
+            };
+            entries.push(Entry { millis, event });
+        }
+        Ok(Transcript { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Echoes every input until it reads 0.
+    const ECHO: &str = "3,9,4,9,1005,9,0,99,0,0";
+
+    fn record(inputs: &[i128]) -> Transcript {
+        let mut program = Intcode::new(ECHO, vec![]);
+        let mut recorder = Recorder::new();
+        for &n in inputs {
+            recorder.exec(&mut program);
+            program.set_input(n);
+        }
+        recorder.exec(&mut program);
+        recorder.transcript()
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let transcript = record(&[3, 2, 0]);
+        let events = transcript
+            .entries
+            .iter()
+            .map(|entry| entry.event)
+            .collect::<Vec<Event>>();
+        use Event::*;
+        assert_eq!(
+            vec![
+                Input(3),
+                Output(3),
+                Input(2),
+                Output(2),
+                Input(0),
+                Output(0)
+            ],
+            events
+        );
+
+        let parsed = transcript.to_string().parse::<Transcript>().unwrap();
+        assert_eq!(transcript, parsed);
+        assert_eq!(Ok(()), parsed.replay(&mut Intcode::new(ECHO, vec![])));
+    }
+
+    #[test]
+    fn divergence() {
+        let transcript = record(&[3, 2, 0]);
+
+        // Doubles the input instead.
+        let mut program = Intcode::new("3,11,1002,11,2,11,4,11,1005,11,0,99", vec![]);
+        assert_eq!(
+            Err(Divergence {
+                index: 1,
+                expected: Some(Event::Output(3)),
+                actual: Some(Event::Output(6)),
+            }),
+            transcript.replay(&mut program)
+        );
+
+        // Halts before the transcript ends.
+        let mut program = Intcode::new("3,5,4,5,99,0", vec![]);
+        assert_eq!(
+            Err(Divergence {
+                index: 2,
+                expected: Some(Event::Input(2)),
+                actual: None,
+            }),
+            transcript.replay(&mut program)
+        );
+    }
+}
@@ -0,0 +1,8 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum State {
+    // The next instruction can be executed.
+    Running,
+    // The next instruction reads input, but there is none.
+    Waiting,
+    Halted,
+}
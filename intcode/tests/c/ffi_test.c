@@ -0,0 +1,42 @@
+/* Runs the day05 comparison example through the C API: prints 999, 1000 or 1001 for an input
+ * below, equal to or above 8. */
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "intcode.h"
+
+static const int64_t COMPARE[] = {
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+    1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105,
+    1, 46, 98, 99};
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <n>\n", argv[0]);
+        return 1;
+    }
+
+    Intcode *program = intcode_new(COMPARE, sizeof(COMPARE) / sizeof(COMPARE[0]));
+    if (intcode_run(program) != INTCODE_WAITING) {
+        return 1;
+    }
+    intcode_push_input(program, strtoll(argv[1], NULL, 10));
+    if (intcode_run(program) != INTCODE_HALTED || intcode_state(program) != INTCODE_HALTED) {
+        return 1;
+    }
+
+    int64_t value;
+    while (intcode_pop_output(program, &value) == 1) {
+        printf("%" PRId64 "\n", value);
+    }
+    printf("steps %" PRIu64 "\n", intcode_steps(program));
+    intcode_free(program);
+
+    /* An invalid opcode is reported instead of unwinding into C. */
+    static const int64_t INVALID[] = {42};
+    program = intcode_new(INVALID, 1);
+    int status = intcode_run(program);
+    intcode_free(program);
+    return status == INTCODE_ERROR ? 0 : 1;
+}
@@ -0,0 +1,159 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{self, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FILES: AtomicUsize = AtomicUsize::new(0);
+
+fn run(code: &str, args: &[&str], stdin: &str) -> Output {
+    let id = FILES.fetch_add(1, Ordering::Relaxed);
+    let path = env::temp_dir().join(format!("intcode-cli-{}-{}.txt", process::id(), id));
+    fs::write(&path, code).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .arg("run")
+        .arg(&path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(path).unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn halt() {
+    let output = run("3,0,4,0,99", &["--input", "42"], "");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("42\n", stdout(&output));
+
+    let output = run("104,1,99", &["--patch", "1=7"], "");
+    assert_eq!("7\n", stdout(&output));
+
+    // day02's example, which leaves its answer at 0.
+    let output = run(
+        "1,9,10,3,2,3,11,0,99,30,40,50",
+        &["--peek", "0", "--peek", "3"],
+        "",
+    );
+    assert_eq!("[0] = 3500\n[3] = 70\n", stdout(&output));
+}
+
+#[test]
+fn starved() {
+    let output = run("104,1,3,0,99", &[], "");
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!(
+        "1\n",
+        stdout(&output),
+        "outputs before the wait are printed"
+    );
+}
+
+#[test]
+fn step_limit() {
+    let output = run("1105,1,0", &["--max-steps", "10"], "");
+    assert_eq!(Some(3), output.status.code());
+}
+
+#[test]
+fn error() {
+    assert_eq!(Some(1), run("42", &[], "").status.code());
+    assert_eq!(Some(1), run("4,-1,99", &[], "").status.code());
+    assert_eq!(Some(1), run("1,x", &[], "").status.code());
+
+    // Overflow in the program, and addresses too far out to patch or peek.
+    let output = run(
+        "1102,170141183460469231731687303715884105727,2,0,99",
+        &[],
+        "",
+    );
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("overflow at 0"));
+    assert_eq!(
+        Some(1),
+        run(
+            "109,170141183460469231731687303715884105727,109,1,99",
+            &[],
+            ""
+        )
+        .status
+        .code()
+    );
+    assert_eq!(
+        Some(1),
+        run("99", &["--peek", "9223372036854775807"], "")
+            .status
+            .code()
+    );
+    assert_eq!(
+        Some(1),
+        run("99", &["--patch", "9223372036854775807=1"], "")
+            .status
+            .code()
+    );
+}
+
+#[test]
+fn ascii() {
+    // Echoes every character until the input runs out.
+    let output = run("3,7,4,7,1105,1,0,0", &["--ascii"], "hi\n");
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!("hi\n", stdout(&output));
+}
+
+#[test]
+fn trace_and_disasm() {
+    let output = run("104,1,99", &["--trace"], "");
+    let trace = String::from_utf8_lossy(&output.stderr);
+    assert!(trace.contains("out 1"), "{}", trace);
+    assert!(trace.contains("hlt"), "{}", trace);
+
+    let output = run("104,1,99", &["--disasm"], "");
+    assert_eq!(
+        "    0  104,1                     out 1\n    2  99                        hlt\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn heatmap() {
+    let dir = env::temp_dir().join(format!("intcode-heatmap-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.ppm");
+
+    // Counts down from 3, so there are enough steps for a few frames.
+    let code = "1101,0,3,20,1001,20,-1,20,1005,20,4,99";
+    let args = [
+        "--heatmap",
+        path.to_str().unwrap(),
+        "--heatmap-width",
+        "8",
+        "--heatmap-every",
+        "2",
+    ];
+    assert_eq!(Some(0), run(code, &args, "").status.code());
+
+    let image = fs::read(&path).unwrap();
+    assert!(image.starts_with(b"P6\n8 3\n255\n"));
+    let frames = fs::read_dir(&dir).unwrap().count() - 1;
+    assert_eq!(
+        4, frames,
+        "after 2, 4 and 6 of 7 steps, and the final counts"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
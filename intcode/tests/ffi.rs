@@ -0,0 +1,38 @@
+// Compiles tests/c/ffi_test.c against include/intcode.h and the cdylib, and runs it.
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    // The shared library is built next to the binary, or in deps/ under `cargo test`.
+    let dir = Path::new(env!("CARGO_BIN_EXE_intcode")).parent().unwrap();
+    let deps = dir.join("deps");
+    let exe = dir.join(format!("ffi_test_{}", std::process::id()));
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let status = Command::new("cc")
+        .arg(manifest.join("tests/c/ffi_test.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg("-L")
+        .arg(dir)
+        .arg("-L")
+        .arg(&deps)
+        .arg("-lintcode")
+        .arg(format!("-Wl,-rpath,{}:{}", dir.display(), deps.display()))
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("cc");
+    assert!(status.success());
+
+    for (input, expected) in [("7", "999"), ("8", "1000"), ("9", "1001")] {
+        let output = Command::new(&exe).arg(input).output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut lines = stdout.lines();
+        assert_eq!(Some(expected), lines.next());
+        assert!(lines.next().unwrap().starts_with("steps "));
+    }
+    std::fs::remove_file(exe).unwrap();
+}
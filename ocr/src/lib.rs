@@ -0,0 +1,309 @@
+// Reads the block letters that some puzzles draw, such as day08's image and day11's hull.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// The 4x6 font. Letters are separated by at least one blank column.
+const SMALL: [(char, &str); 18] = [
+    ('A', ".##.|#..#|#..#|####|#..#|#..#"),
+    ('B', "###.|#..#|###.|#..#|#..#|###."),
+    ('C', ".##.|#..#|#...|#...|#..#|.##."),
+    ('E', "####|#...|###.|#...|#...|####"),
+    ('F', "####|#...|###.|#...|#...|#..."),
+    ('G', ".##.|#..#|#...|#.##|#..#|.###"),
+    ('H', "#..#|#..#|####|#..#|#..#|#..#"),
+    ('I', "###|.#.|.#.|.#.|.#.|###"),
+    ('J', "..##|...#|...#|...#|#..#|.##."),
+    ('K', "#..#|#.#.|##..|#.#.|#.#.|#..#"),
+    ('L', "#...|#...|#...|#...|#...|####"),
+    ('O', ".##.|#..#|#..#|#..#|#..#|.##."),
+    ('P', "###.|#..#|#..#|###.|#...|#..."),
+    ('R', "###.|#..#|#..#|###.|#.#.|#..#"),
+    ('S', ".###|#...|#...|.##.|...#|###."),
+    ('U', "#..#|#..#|#..#|#..#|#..#|.##."),
+    ('Y', "#...#|#...#|.#.#.|..#..|..#..|..#.."),
+    ('Z', "####|...#|..#.|.#..|#...|####"),
+];
+
+// The 6x10 font.
+const LARGE: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..|.#..#.|#....#|#....#|#....#|######|#....#|#....#|#....#|#....#",
+    ),
+    (
+        'B',
+        "#####.|#....#|#....#|#....#|#####.|#....#|#....#|#....#|#....#|#####.",
+    ),
+    (
+        'C',
+        ".####.|#....#|#.....|#.....|#.....|#.....|#.....|#.....|#....#|.####.",
+    ),
+    (
+        'E',
+        "######|#.....|#.....|#.....|#####.|#.....|#.....|#.....|#.....|######",
+    ),
+    (
+        'F',
+        "######|#.....|#.....|#.....|#####.|#.....|#.....|#.....|#.....|#.....",
+    ),
+    (
+        'G',
+        ".####.|#....#|#.....|#.....|#.....|#..###|#....#|#....#|#...##|.###.#",
+    ),
+    (
+        'H',
+        "#....#|#....#|#....#|#....#|######|#....#|#....#|#....#|#....#|#....#",
+    ),
+    (
+        'J',
+        "...###|....#.|....#.|....#.|....#.|....#.|....#.|#...#.|#...#.|.###..",
+    ),
+    (
+        'K',
+        "#....#|#...#.|#..#..|#.#...|##....|##....|#.#...|#..#..|#...#.|#....#",
+    ),
+    (
+        'L',
+        "#.....|#.....|#.....|#.....|#.....|#.....|#.....|#.....|#.....|######",
+    ),
+    (
+        'N',
+        "#....#|##...#|##...#|#.#..#|#.#..#|#..#.#|#..#.#|#...##|#...##|#....#",
+    ),
+    (
+        'P',
+        "#####.|#....#|#....#|#....#|#####.|#.....|#.....|#.....|#.....|#.....",
+    ),
+    (
+        'R',
+        "#####.|#....#|#....#|#....#|#####.|#..#..|#...#.|#...#.|#....#|#....#",
+    ),
+    (
+        'X',
+        "#....#|#....#|.#..#.|.#..#.|..##..|..##..|.#..#.|.#..#.|#....#|#....#",
+    ),
+    (
+        'Z',
+        "######|.....#|.....#|....#.|...#..|..#...|.#....|#.....|#.....|######",
+    ),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum OcrError {
+    // The letters are neither 6 nor 10 rows high.
+    Height(usize),
+    // Some glyphs are not letters of the font. `text` has a `?` in their place, and every glyph
+    // is listed with the column it starts at.
+    Unrecognized {
+        text: String,
+        glyphs: Vec<(usize, String)>,
+    },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::Height(height) => write!(f, "no font is {} rows high", height),
+            OcrError::Unrecognized { text, glyphs } => {
+                write!(f, "unrecognized glyphs in {:?}:", text)?;
+                for (column, glyph) in glyphs {
+                    write!(f, "\ncolumn {}:\n{}", column, glyph.replace('|', "\n"))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+// Reads rows of pixels where non-zero pixels are lit, e.g. day08's decoded image.
+pub fn from_rows(rows: &[Vec<i32>]) -> Result<String, OcrError> {
+    let bitmap = rows
+        .iter()
+        .map(|row| row.iter().map(|&pixel| pixel != 0).collect())
+        .collect::<Vec<Vec<bool>>>();
+    read(&bitmap)
+}
+
+// Reads a sparse image, such as day11's painted panels. Only the bounding box of the points is
+// considered, and points missing from the map are unlit.
+pub fn from_points<V, F>(points: &HashMap<(i32, i32), V>, lit: F) -> Result<String, OcrError>
+where
+    F: Fn(&V) -> bool,
+{
+    let lit = points
+        .iter()
+        .filter(|(_, value)| lit(value))
+        .map(|(&pos, _)| pos)
+        .collect::<Vec<(i32, i32)>>();
+    if lit.is_empty() {
+        return Ok(String::new());
+    }
+
+    let min_x = lit.iter().map(|pos| pos.0).min().unwrap();
+    let max_x = lit.iter().map(|pos| pos.0).max().unwrap();
+    let min_y = lit.iter().map(|pos| pos.1).min().unwrap();
+    let max_y = lit.iter().map(|pos| pos.1).max().unwrap();
+    let mut bitmap = vec![vec![false; (max_x - min_x + 1) as usize]; (max_y - min_y + 1) as usize];
+    for (x, y) in lit {
+        bitmap[(y - min_y) as usize][(x - min_x) as usize] = true;
+    }
+    read(&bitmap)
+}
+
+// Reads the letters of a bitmap. Blank rows above and below the letters are ignored, and the
+// font is picked from the height of what is left.
+pub fn read(bitmap: &[Vec<bool>]) -> Result<String, OcrError> {
+    let blank = |row: &Vec<bool>| row.iter().all(|&pixel| !pixel);
+    let top = bitmap.iter().position(|row| !blank(row));
+    let bottom = bitmap.iter().rposition(|row| !blank(row));
+    let rows = match (top, bottom) {
+        (Some(top), Some(bottom)) => &bitmap[top..=bottom],
+        _ => return Ok(String::new()),
+    };
+
+    let font: &[(char, &str)] = match rows.len() {
+        6 => &SMALL,
+        10 => &LARGE,
+        height => return Err(OcrError::Height(height)),
+    };
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let pixel = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+    let lit_column = |x: usize| (0..rows.len()).any(|y| pixel(x, y));
+
+    let mut text = String::new();
+    let mut unrecognized = vec![];
+    let mut x = 0;
+    while x < width {
+        if !lit_column(x) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < width && lit_column(x) {
+            x += 1;
+        }
+        let glyph = (0..rows.len())
+            .map(|y| {
+                (start..x)
+                    .map(|x| if pixel(x, y) { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("|");
+
+        match font.iter().find(|(_, letter)| trim(letter) == glyph) {
+            Some(&(c, _)) => text.push(c),
+            None => {
+                text.push('?');
+                unrecognized.push((start, glyph));
+            }
+        }
+    }
+
+    if unrecognized.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::Unrecognized {
+            text,
+            glyphs: unrecognized,
+        })
+    }
+}
+
+// Drops the blank columns at the sides of a letter, as `read` does for the glyphs it cuts out.
+fn trim(letter: &str) -> String {
+    let rows = letter.split('|').collect::<Vec<&str>>();
+    let lit = |x: usize| rows.iter().any(|row| row.as_bytes()[x] == b'#');
+    let width = rows[0].len();
+    let start = (0..width).find(|&x| lit(x)).unwrap_or(0);
+    let end = (0..width).rfind(|&x| lit(x)).map_or(0, |x| x + 1);
+    rows.iter()
+        .map(|row| &row[start..end])
+        .collect::<Vec<&str>>()
+        .join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws the letters of a font side by side, `gap` columns apart.
+    fn draw(font: &[(char, &str)], text: &str, gap: usize) -> Vec<Vec<bool>> {
+        let letters = text
+            .chars()
+            .map(|c| font.iter().find(|(letter, _)| *letter == c).unwrap().1)
+            .map(|letter| letter.split('|').collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+        (0..letters[0].len())
+            .map(|y| {
+                letters
+                    .iter()
+                    .map(|letter| format!("{}{}", letter[y], ".".repeat(gap)))
+                    .collect::<String>()
+                    .chars()
+                    .map(|c| c == '#')
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small() {
+        let alphabet = SMALL.iter().map(|(c, _)| c).collect::<String>();
+        assert_eq!(Ok(alphabet.clone()), read(&draw(&SMALL, &alphabet, 1)));
+    }
+
+    #[test]
+    fn large() {
+        let alphabet = LARGE.iter().map(|(c, _)| c).collect::<String>();
+        assert_eq!(Ok(alphabet.clone()), read(&draw(&LARGE, &alphabet, 2)));
+    }
+
+    #[test]
+    fn rows_and_points() {
+        let rows = vec![
+            vec![0, 0, 0, 0, 0],
+            vec![0, 1, 1, 1, 1],
+            vec![0, 1, 0, 0, 0],
+            vec![0, 1, 1, 1, 0],
+            vec![0, 1, 0, 0, 0],
+            vec![0, 1, 0, 0, 0],
+            vec![0, 1, 0, 0, 0],
+        ];
+        assert_eq!(Ok("F".to_string()), from_rows(&rows));
+
+        // The same letter, with negative coordinates and unlit points.
+        let mut points = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                points.insert((x as i32 - 10, y as i32 - 3), pixel);
+            }
+        }
+        assert_eq!(
+            Ok("F".to_string()),
+            from_points(&points, |&pixel| pixel == 1)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut bitmap = draw(&SMALL, "HI", 1);
+        // Turn the H into something else.
+        bitmap[0][0] = false;
+        assert_eq!(
+            Err(OcrError::Unrecognized {
+                text: "?I".to_string(),
+                glyphs: vec![(0, "...#|#..#|####|#..#|#..#|#..#".to_string())],
+            }),
+            read(&bitmap)
+        );
+
+        assert_eq!(Err(OcrError::Height(2)), read(&[vec![true], vec![true]]));
+        assert_eq!("no font is 2 rows high", OcrError::Height(2).to_string());
+    }
+}